@@ -0,0 +1,59 @@
+// Generates the migration registry from src/database/sql/migrations
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MIGRATIONS_DIR: &str = "src/database/sql/migrations";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", MIGRATIONS_DIR);
+
+    let manifest_dir: PathBuf = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
+    let dir: PathBuf = manifest_dir.join(MIGRATIONS_DIR);
+
+    // version -> file stem, e.g. 1 -> "0001_initial_schema"
+    let mut migrations: BTreeMap<i64, String> = BTreeMap::new();
+    for entry in fs::read_dir(&dir).expect("Failed to read migrations directory") {
+        let file_name: String = entry.expect("Failed to read migration entry").file_name().to_string_lossy().into_owned();
+        let Some(stem) = file_name.strip_suffix(".sql") else { continue };
+        if stem.ends_with(".down") {
+            continue;
+        }
+
+        let version: i64 = stem
+            .split('_')
+            .next()
+            .and_then(|v: &str| v.parse().ok())
+            .unwrap_or_else(|| panic!("Migration {} must start with a numeric version (NNNN_name.sql)", file_name));
+
+        if let Some(existing) = migrations.insert(version, stem.to_string()) {
+            panic!("Migrations {} and {} share version {}", existing, stem, version);
+        }
+    }
+
+    let mut registry: String = String::from("&[\n");
+    for (version, stem) in &migrations {
+        let up: PathBuf = dir.join(format!("{}.sql", stem));
+        let down: PathBuf = dir.join(format!("{}.down.sql", stem));
+        let down_expr: String = if down.exists() {
+            format!("Some(include_str!({:?}))", path_str(&down))
+        } else {
+            "None".to_string()
+        };
+
+        registry.push_str(&format!(
+            "    Migration {{ version: {}, name: {:?}, sql: include_str!({:?}), down: {} }},\n",
+            version, stem, path_str(&up), down_expr
+        ));
+    }
+    registry.push(']');
+
+    let out: PathBuf = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR")).join("migrations.rs");
+    fs::write(out, registry).expect("Failed to write migration registry");
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
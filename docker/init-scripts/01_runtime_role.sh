@@ -0,0 +1,19 @@
+#!/bin/sh
+# Creates the API's runtime login role (DB_USER) on first database start.
+# POSTGRES_USER (DB_MIGRATION_USER) owns the schema and runs migrations; the runtime
+# role only gets data access through app_runtime, so row level security applies to it.
+# The role name and password reach psql as variables (:"user", :'pw') so they are quoted by psql.
+set -e
+
+psql -v ON_ERROR_STOP=1 -v user="$DB_USER" -v pw="$DB_PASSWORD" \
+    --username "$POSTGRES_USER" --dbname "$POSTGRES_DB" <<'EOSQL'
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_runtime') THEN
+        CREATE ROLE app_runtime NOLOGIN NOSUPERUSER NOBYPASSRLS;
+    END IF;
+END
+$$;
+
+CREATE ROLE :"user" LOGIN PASSWORD :'pw' IN ROLE app_runtime;
+EOSQL
@@ -1,13 +1,15 @@
-use axum::{extract::{State, Extension}, Json, http::StatusCode};
+use axum::{extract::{State, Extension}, Json, http::{header::AUTHORIZATION, HeaderMap, StatusCode}};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use uuid::Uuid;
 use bcrypt::{hash, verify, DEFAULT_COST};
-use redis::AsyncCommands; // For Redis async operations
 
 use crate::config::state::AppState;
 use crate::utils::response_handler::HandlerResponse;
-use crate::api::middleware::tenant::TenantContext;
+use crate::api::middleware::tenant::{cache_unavailable, TenantContext};
+use crate::api::middleware::tenant_tx::RequestTx;
+use crate::database::{Session, TenantTx};
+use crate::api::plans::quota::{enforce_user_quota, QuotaExceeded};
 
 // =============================================================================
 // DTOs
@@ -38,11 +40,18 @@ pub struct AuthResponse {
 
 /// Register a new user for the current tenant
 pub async fn register(
-    State(state): State<AppState>,
     Extension(ctx): Extension<TenantContext>,
+    mut tx: RequestTx,
     Json(payload): Json<RegisterRequest>,
 ) -> HandlerResponse {
-    // 1. Hash Password
+    // 1. Respect the tenant's signup policy
+    if !ctx.settings.allow_signup {
+        return HandlerResponse::new(StatusCode::FORBIDDEN)
+            .message("Signup is disabled for this tenant")
+            .data(json!({ "error": "signup_disabled" }));
+    }
+
+    // 2. Hash Password
     let password_hash: String = match hash(payload.password.as_bytes(), DEFAULT_COST) {
         Ok(h) => h,
         Err(e) => {
@@ -52,12 +61,10 @@ pub async fn register(
         }
     };
 
-    // 2. Insert User (Scoped Execution)
-    // We use with_tenant to ensure the query runs with "SET LOCAL app.current_tenant_id = ..."
-    // We must cast the transaction to &mut sqlx::PgConnection or Executor
-    let result: anyhow::Result<sqlx::postgres::PgRow> = state.database.with_tenant(ctx.tenant_id, |tx| Box::pin(async move {
-        // We need to reborrow tx as mutable for sqlx
-        sqlx::query(
+    // 3. Insert User in the request's tenant transaction (committed on a successful response)
+    let result: anyhow::Result<sqlx::postgres::PgRow> = async {
+        let conn: &mut sqlx::PgConnection = tx.conn().await?;
+        let row: sqlx::postgres::PgRow = sqlx::query(
             r#"
             INSERT INTO users (tenant_id, email, password_hash, full_name)
             VALUES ($1, $2, $3, $4)
@@ -68,10 +75,13 @@ pub async fn register(
         .bind(payload.email)
         .bind(password_hash)
         .bind(payload.full_name)
-        .fetch_one(&mut **tx)
-        .await
-        .map_err(|e| e.into()) // Convert sqlx::Error to anyhow::Error
-    })).await;
+        .fetch_one(&mut *conn)
+        .await?;
+
+        // Counted with the new user; over the quota the 402 rolls the insert back
+        enforce_user_quota(conn, ctx.tenant_id).await?;
+        Ok(row)
+    }.await;
 
     match result {
         Ok(row) => {
@@ -83,15 +93,17 @@ pub async fn register(
                 .data(json!({ "user_id": user_id }))
         }
         Err(e) => {
+            if let Some(quota_error) = e.downcast_ref::<QuotaExceeded>() {
+                return quota_error.clone().into();
+            }
+
             // Handle duplicate email error (Postgres error code 23505)
-            if let Some(db_error) = e.downcast_ref::<sqlx::Error>() {
-                 if let sqlx::Error::Database(db_err) = db_error {
-                     if db_err.code().as_deref() == Some("23505") {
-                        return HandlerResponse::new(StatusCode::CONFLICT)
-                            .message("Email already registered")
-                            .data(json!({ "error": "duplicate_email" }));
-                     }
-                 }
+            if let Some(sqlx::Error::Database(db_err)) = e.downcast_ref::<sqlx::Error>() {
+                if db_err.code().as_deref() == Some("23505") {
+                    return HandlerResponse::new(StatusCode::CONFLICT)
+                        .message("Email already registered")
+                        .data(json!({ "error": "duplicate_email" }));
+                }
             }
 
             tracing::error!("Registration failed: {}", e);
@@ -109,25 +121,16 @@ pub async fn login(
     Json(payload): Json<LoginRequest>,
 ) -> HandlerResponse {
     // 1. Fetch User (Scoped Execution)
-    // Clone email for the query closure
-    let email_for_query: String = payload.email.clone();
-    let user_result: anyhow::Result<Option<sqlx::postgres::PgRow>> = state.database.with_tenant(ctx.tenant_id, |tx| Box::pin(async move {
-        sqlx::query(
-            r#"
-            SELECT id, password_hash
-            FROM users
-            WHERE email = $1
-            "#
-        )
-        .bind(email_for_query) // RLS handles tenant_id filtering automatically? 
-                             // IMPORTANT: We manually bind tenant_id in INSERT, but for SELECT 
-                             // the RLS policy `tenant_id = current_setting(...)` handles it.
-                             // However, the users table has a composite unique key (tenant_id, email).
-                             // So multiple tenants can have the same email.
-        .fetch_optional(&mut **tx)
-        .await
-        .map_err(|e| e.into()) // Convert sqlx::Error to anyhow::Error
-    })).await;
+    // RLS limits the lookup to the current tenant; emails are only unique per tenant
+    let user_result: anyhow::Result<Option<sqlx::postgres::PgRow>> = async {
+        let mut tx: TenantTx = state.database.begin_tenant(ctx.tenant_id).await?;
+        let row: Option<sqlx::postgres::PgRow> = sqlx::query("SELECT id, password_hash FROM users WHERE email = $1")
+            .bind(&payload.email)
+            .fetch_optional(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(row)
+    }.await;
 
     match user_result {
         Ok(Some(row)) => {
@@ -139,36 +142,20 @@ pub async fn login(
             if verify(payload.password.as_bytes(), &stored_hash).unwrap_or(false) {
                 // 3. Generate Session Token
                 let session_token: String = Uuid::new_v4().to_string();
-                let redis_key: String = format!("session:{}", session_token);
-
-                // 4. Store Session in Redis (TTL 24h)
-                let mut conn: redis::aio::MultiplexedConnection = match state.redis.get_connection().await {
-                    Ok(c) => c,
-                    Err(e) => {
-                        return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
-                            .message("Failed to connect to cache")
-                            .data(json!({ "error": e.to_string() }));
-                    }
-                };
 
-                // Store user info in session
-                let session_data: String = json!({
-                    "user_id": user_id,
-                    "tenant_id": ctx.tenant_id,
-                    "email": payload.email
-                }).to_string();
-
-                let set_result: redis::RedisResult<()> = conn.set_ex(&redis_key, session_data, 24 * 60 * 60).await;
-                
-                match set_result {
-                    Ok(_) => (),
-                    Err(e) => {
-                         return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
-                            .message("Failed to create session")
-                            .data(json!({ "error": e.to_string() }));
-                    }
+                // 4. Store Session in the cache (TTL from tenant settings)
+                let session: Session = Session {
+                    user_id,
+                    tenant_id: ctx.tenant_id,
+                    email: payload.email,
                 };
 
+                // Sessions only live in the cache, so no session can be issued while it is down
+                if let Err(e) = state.cache.create_session(&session_token, &session, ctx.settings.session_ttl_seconds).await {
+                    tracing::error!("Failed to create session: {}", e);
+                    return cache_unavailable();
+                }
+
                 // 5. Return Token
                 HandlerResponse::new(StatusCode::OK)
                     .message("Login successful")
@@ -195,3 +182,50 @@ pub async fn login(
     }
 }
 
+
+/// Returns the session of the bearer token, which must belong to the request's tenant
+pub async fn get_session(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    headers: HeaderMap,
+) -> HandlerResponse {
+    match authenticate(&state, &ctx, &headers).await {
+        Ok(session) => HandlerResponse::new(StatusCode::OK)
+            .message("Session is valid")
+            .data(json!({
+                "user_id": session.user_id,
+                "tenant_id": session.tenant_id,
+                "email": session.email,
+            })),
+        Err(response) => response,
+    }
+}
+
+/// Resolves the bearer session of a request. When the cache is down,
+/// CACHE_SESSION_FAILURE_POLICY decides between 503 (fail-closed) and treating the
+/// session as missing (fail-open); sessions have no database fallback.
+pub async fn authenticate(state: &AppState, ctx: &TenantContext, headers: &HeaderMap) -> Result<Session, HandlerResponse> {
+    let unauthorized = || -> HandlerResponse {
+        HandlerResponse::new(StatusCode::UNAUTHORIZED)
+            .message("Invalid or expired session")
+            .data(json!({ "error": "invalid_session" }))
+    };
+
+    let token: &str = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(unauthorized)?;
+
+    let session: Option<Session> = state.environment.cache_session_failure_policy
+        .read("sessions", state.cache.get_session(token).await)
+        .map_err(|e| {
+            tracing::error!("Cache unavailable for session lookup: {}", e);
+            cache_unavailable()
+        })?;
+
+    // Tokens are only valid for the tenant that issued them
+    session
+        .filter(|session: &Session| session.tenant_id == ctx.tenant_id)
+        .ok_or_else(unauthorized)
+}
@@ -1,4 +1,4 @@
-use axum::{routing::post, Router};
+use axum::{routing::{get, post}, Router};
 use crate::config::state::AppState;
 use super::handler;
 
@@ -6,5 +6,6 @@ pub fn auth_routes() -> Router<AppState> {
     Router::new()
         .route("/auth/register", post(handler::register))
         .route("/auth/login", post(handler::login))
+        .route("/auth/session", get(handler::get_session))
 }
 
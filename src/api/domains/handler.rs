@@ -0,0 +1,398 @@
+// Tenant custom domain handlers
+
+use axum::{extract::{Extension, Path, State}, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::api::middleware::read_session::ReadSession;
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::core::domain_verifier::{verification_record_name, verification_record_value};
+use crate::database::CachedDomain;
+use crate::utils::response_handler::HandlerResponse;
+
+// =============================================================================
+// DTOs
+// =============================================================================
+
+#[derive(Deserialize)]
+pub struct CreateDomainRequest {
+    pub hostname: String,
+}
+
+/// Verification state of a custom domain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainStatus {
+    /// Waiting for the TXT record to be published and checked
+    Pending,
+    /// Ownership proven; the hostname resolves to the tenant
+    Verified,
+    /// Last check did not find the TXT record; can be re-checked
+    Failed,
+}
+
+impl DomainStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DomainStatus::Pending => "pending",
+            DomainStatus::Verified => "verified",
+            DomainStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Custom domain row as stored in `tenant_domains`
+#[derive(Debug, sqlx::FromRow)]
+pub struct TenantDomain {
+    pub id: Uuid,
+    pub hostname: String,
+    pub verification_token: String,
+    pub status: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl TenantDomain {
+    /// Serializes the domain along with the DNS record the tenant must publish
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "hostname": self.hostname,
+            "status": self.status,
+            "verified_at": self.verified_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+            "last_checked_at": self.last_checked_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+            "created_at": self.created_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+            "verification": {
+                "type": "TXT",
+                "name": verification_record_name(&self.hostname),
+                "value": verification_record_value(&self.verification_token),
+            }
+        })
+    }
+}
+
+const DOMAIN_COLUMNS: &str = "id, hostname, verification_token, status, verified_at, last_checked_at, created_at";
+
+// =============================================================================
+// HANDLERS
+// =============================================================================
+
+/// Registers a custom domain for the current tenant in `pending` state
+pub async fn create_domain(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Json(payload): Json<CreateDomainRequest>,
+) -> HandlerResponse {
+    let hostname: String = match validate_hostname(&payload.hostname) {
+        Ok(h) => h,
+        Err(reason) => {
+            return HandlerResponse::new(StatusCode::BAD_REQUEST)
+                .message("Invalid hostname")
+                .data(json!({ "error": "invalid_hostname", "details": reason }));
+        }
+    };
+
+    let verification_token: String = Uuid::new_v4().simple().to_string();
+
+    let result: anyhow::Result<TenantDomain> = state.database.with_tenant(ctx.tenant_id, |tx| Box::pin(async move {
+        let query: String = format!(
+            "INSERT INTO tenant_domains (tenant_id, hostname, verification_token) VALUES ($1, $2, $3) RETURNING {}",
+            DOMAIN_COLUMNS
+        );
+
+        sqlx::query_as::<_, TenantDomain>(&query)
+            .bind(ctx.tenant_id)
+            .bind(hostname)
+            .bind(verification_token)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await;
+
+    match result {
+        Ok(domain) => HandlerResponse::new(StatusCode::CREATED)
+            .message("Domain registered, publish the TXT record to verify it")
+            .data(domain.to_json()),
+        Err(e) if is_unique_violation(&e) => HandlerResponse::new(StatusCode::CONFLICT)
+            .message("Domain already registered")
+            .data(json!({ "error": "duplicate_domain" })),
+        Err(e) => {
+            tracing::error!("Domain registration failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Domain registration failed")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Lists the custom domains of the current tenant
+pub async fn list_domains(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Extension(session): Extension<ReadSession>,
+) -> HandlerResponse {
+    // Read-only, so it may be served by a replica
+    let result: anyhow::Result<Vec<TenantDomain>> = state.database.with_tenant_read(ctx.tenant_id, session.key(), |tx| Box::pin(async move {
+        let query: String = format!("SELECT {} FROM tenant_domains ORDER BY created_at", DOMAIN_COLUMNS);
+
+        sqlx::query_as::<_, TenantDomain>(&query)
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await;
+
+    match result {
+        Ok(domains) => {
+            let items: Vec<Value> = domains.iter().map(TenantDomain::to_json).collect();
+            HandlerResponse::new(StatusCode::OK)
+                .message("Domains retrieved successfully")
+                .data(json!({ "domains": items, "count": items.len() }))
+        }
+        Err(e) => {
+            tracing::error!("Listing domains failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve domains")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Returns a single custom domain of the current tenant
+pub async fn get_domain(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Path(domain_id): Path<Uuid>,
+) -> HandlerResponse {
+    match fetch_domain(&state, ctx.tenant_id, domain_id).await {
+        Ok(Some(domain)) => HandlerResponse::new(StatusCode::OK)
+            .message("Domain retrieved successfully")
+            .data(domain.to_json()),
+        Ok(None) => domain_not_found(),
+        Err(e) => {
+            tracing::error!("Fetching domain failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve domain")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Checks the domain's TXT record and marks it `verified` or `failed`
+pub async fn verify_domain(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Path(domain_id): Path<Uuid>,
+) -> HandlerResponse {
+    // 1. Load the domain (Scoped Execution)
+    let domain: TenantDomain = match fetch_domain(&state, ctx.tenant_id, domain_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return domain_not_found(),
+        Err(e) => {
+            tracing::error!("Fetching domain failed: {}", e);
+            return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve domain")
+                .data(json!({ "error": e.to_string() }));
+        }
+    };
+
+    if domain.status == DomainStatus::Verified.as_str() {
+        return HandlerResponse::new(StatusCode::OK)
+            .message("Domain already verified")
+            .data(domain.to_json());
+    }
+
+    // 2. Look up the TXT record
+    let is_verified: bool = match state.domain_verifier.verify(&domain.hostname, &domain.verification_token).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("DNS lookup for {} failed: {}", domain.hostname, e);
+            return HandlerResponse::new(StatusCode::BAD_GATEWAY)
+                .message("DNS lookup failed, try again later")
+                .data(json!({ "error": "dns_lookup_failed", "details": e.to_string() }));
+        }
+    };
+
+    let new_status: DomainStatus = if is_verified { DomainStatus::Verified } else { DomainStatus::Failed };
+
+    // 3. Persist the outcome
+    let result: anyhow::Result<TenantDomain> = state.database.with_tenant(ctx.tenant_id, |tx| Box::pin(async move {
+        let query: String = format!(
+            r#"
+            UPDATE tenant_domains
+            SET status = $2,
+                last_checked_at = NOW(),
+                verified_at = CASE WHEN $2 = 'verified' THEN NOW() ELSE NULL END
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            DOMAIN_COLUMNS
+        );
+
+        sqlx::query_as::<_, TenantDomain>(&query)
+            .bind(domain_id)
+            .bind(new_status.as_str())
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await;
+
+    let updated: TenantDomain = match result {
+        Ok(d) => d,
+        Err(e) if is_unique_violation(&e) => {
+            return HandlerResponse::new(StatusCode::CONFLICT)
+                .message("Domain is already verified by another tenant")
+                .data(json!({ "error": "domain_already_claimed" }));
+        }
+        Err(e) => {
+            tracing::error!("Updating domain status failed: {}", e);
+            return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Domain verification failed")
+                .data(json!({ "error": e.to_string() }));
+        }
+    };
+
+    if !is_verified {
+        return HandlerResponse::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .message("Verification TXT record not found")
+            .data(json!({ "error": "verification_record_not_found", "domain": updated.to_json() }));
+    }
+
+    // 4. Replace any cached "unknown domain" marker
+    if let Err(e) = state.cache.set_domain_tenant(&updated.hostname, CachedDomain::Tenant(ctx.tenant_id)).await {
+        tracing::warn!("Failed to cache domain {}: {}", updated.hostname, e);
+    }
+
+    HandlerResponse::new(StatusCode::OK)
+        .message("Domain verified successfully")
+        .data(updated.to_json())
+}
+
+/// Removes a custom domain from the current tenant
+pub async fn delete_domain(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Path(domain_id): Path<Uuid>,
+) -> HandlerResponse {
+    let result: anyhow::Result<Option<String>> = state.database.with_tenant(ctx.tenant_id, |tx| Box::pin(async move {
+        sqlx::query_scalar("DELETE FROM tenant_domains WHERE id = $1 RETURNING hostname")
+            .bind(domain_id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await;
+
+    match result {
+        Ok(Some(hostname)) => {
+            if let Err(e) = state.cache.invalidate_domain(&hostname).await {
+                tracing::warn!("Failed to invalidate cached domain {}: {}", hostname, e);
+            }
+
+            HandlerResponse::new(StatusCode::OK)
+                .message("Domain deleted successfully")
+                .data(json!({ "id": domain_id, "hostname": hostname }))
+        }
+        Ok(None) => domain_not_found(),
+        Err(e) => {
+            tracing::error!("Deleting domain failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to delete domain")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+async fn fetch_domain(state: &AppState, tenant_id: Uuid, domain_id: Uuid) -> anyhow::Result<Option<TenantDomain>> {
+    state.database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        let query: String = format!("SELECT {} FROM tenant_domains WHERE id = $1", DOMAIN_COLUMNS);
+
+        sqlx::query_as::<_, TenantDomain>(&query)
+            .bind(domain_id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await
+}
+
+fn domain_not_found() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::NOT_FOUND)
+        .message("Domain not found")
+        .data(json!({ "error": "domain_not_found" }))
+}
+
+/// Postgres error code 23505
+fn is_unique_violation(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<sqlx::Error>(),
+        Some(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("23505")
+    )
+}
+
+/// Validates and normalizes a hostname submitted by a tenant
+pub fn validate_hostname(input: &str) -> Result<String, &'static str> {
+    let hostname: String = input.trim().trim_end_matches('.').to_ascii_lowercase();
+
+    if hostname.is_empty() || hostname.len() > 253 {
+        return Err("hostname must be between 1 and 253 characters");
+    }
+
+    let labels: Vec<&str> = hostname.split('.').collect();
+    if labels.len() < 2 {
+        return Err("hostname must be a fully qualified domain name");
+    }
+
+    for label in &labels {
+        if label.is_empty() || label.len() > 63 {
+            return Err("each hostname label must be between 1 and 63 characters");
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err("hostname labels cannot start or end with a hyphen");
+        }
+        if !label.chars().all(|c: char| c.is_ascii_alphanumeric() || c == '-') {
+            return Err("hostname can only contain letters, digits, hyphens and dots");
+        }
+    }
+
+    if labels.last().is_some_and(|tld: &&str| tld.chars().all(|c: char| c.is_ascii_digit())) {
+        return Err("IP addresses cannot be used as custom domains");
+    }
+
+    Ok(hostname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostnames_are_normalized() {
+        assert_eq!(validate_hostname(" Shop.Example.COM. "), Ok("shop.example.com".to_string()));
+        assert_eq!(validate_hostname("a-b.example.co.uk"), Ok("a-b.example.co.uk".to_string()));
+    }
+
+    #[test]
+    fn invalid_hostnames_are_rejected() {
+        let long_label: String = format!("{}.com", "a".repeat(64));
+        let long_name: String = format!("{}.com", vec!["a".repeat(60); 5].join("."));
+        for input in [
+            "",
+            "localhost",
+            "example..com",
+            "-shop.example.com",
+            "shop-.example.com",
+            "shop_1.example.com",
+            "shop.example.com/path",
+            "192.168.0.1",
+            long_label.as_str(),
+            long_name.as_str(),
+        ] {
+            assert!(validate_hostname(input).is_err(), "{:?} was accepted", input);
+        }
+    }
+}
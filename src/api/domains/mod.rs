@@ -0,0 +1,4 @@
+// Tenant custom domain module
+
+pub mod handler;
+pub mod routes;
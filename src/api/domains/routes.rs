@@ -0,0 +1,13 @@
+// Tenant custom domain route definitions
+
+use axum::{routing::{get, post}, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Creates router with custom domain management endpoints for the current tenant
+pub fn domain_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenant/domains", post(handler::create_domain).get(handler::list_domains))
+        .route("/tenant/domains/{domain_id}", get(handler::get_domain).delete(handler::delete_domain))
+        .route("/tenant/domains/{domain_id}/verify", post(handler::verify_domain))
+}
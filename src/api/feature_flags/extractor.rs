@@ -0,0 +1,30 @@
+// `Flags` extractor giving handlers the feature flag state of the current tenant
+
+use axum::{extract::FromRequestParts, http::{request::Parts, StatusCode}};
+use serde_json::json;
+
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::feature_flags::Flags;
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+use super::handler::load_flags;
+
+impl FromRequestParts<AppState> for Flags {
+    type Rejection = HandlerResponse;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Requires the tenant middleware to have run
+        let ctx: &TenantContext = parts.extensions.get::<TenantContext>().ok_or_else(|| {
+            tracing::error!("Flags extractor used on a route without tenant context");
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Internal Service Error")
+        })?;
+
+        load_flags(state, ctx.tenant_id).await.map_err(|e| {
+            tracing::error!("Failed to load feature flags for tenant {}: {}", ctx.tenant_id, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Internal Service Error")
+                .data(json!({ "error": "feature_flags_unavailable" }))
+        })
+    }
+}
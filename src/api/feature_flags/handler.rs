@@ -0,0 +1,352 @@
+// Feature flag handlers
+
+use std::collections::HashMap;
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::config::feature_flags::{is_valid_flag_key, FlagDefinition, FlagEvaluation, Flags};
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+
+// =============================================================================
+// DTOs
+// =============================================================================
+
+#[derive(Deserialize)]
+pub struct UpsertFlagRequest {
+    pub description: Option<String>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub rollout_percentage: i16,
+}
+
+#[derive(Deserialize)]
+pub struct TenantOverrideRequest {
+    pub enabled: bool,
+}
+
+// =============================================================================
+// TENANT HANDLERS
+// =============================================================================
+
+/// Returns every flag evaluated for the current tenant
+pub async fn list_tenant_flags(flags: Flags) -> HandlerResponse {
+    let evaluated: HashMap<String, FlagEvaluation> = flags.evaluate_all();
+
+    HandlerResponse::new(StatusCode::OK)
+        .message("Feature flags retrieved successfully")
+        .data(json!({ "flags": evaluated }))
+}
+
+// =============================================================================
+// ADMIN HANDLERS
+// =============================================================================
+
+/// Lists all global flag definitions
+pub async fn list_flags(State(state): State<AppState>) -> HandlerResponse {
+    match fetch_flag_definitions(&state).await {
+        Ok(definitions) => HandlerResponse::new(StatusCode::OK)
+            .message("Feature flags retrieved successfully")
+            .data(json!({ "flags": definitions, "count": definitions.len() })),
+        Err(e) => {
+            tracing::error!("Listing feature flags failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve feature flags")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Creates or updates a global flag definition
+pub async fn upsert_flag(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Json(payload): Json<UpsertFlagRequest>,
+) -> HandlerResponse {
+    if !is_valid_flag_key(&key) {
+        return invalid_flag_key();
+    }
+
+    if !(0..=100).contains(&payload.rollout_percentage) {
+        return HandlerResponse::new(StatusCode::BAD_REQUEST)
+            .message("Invalid rollout percentage")
+            .data(json!({ "error": "invalid_rollout_percentage", "details": "rollout_percentage must be between 0 and 100" }));
+    }
+
+    let result: anyhow::Result<FlagDefinition> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+
+        let definition: FlagDefinition = sqlx::query_as(
+            r#"
+            INSERT INTO feature_flags (key, description, enabled, rollout_percentage)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (key) DO UPDATE
+            SET description = EXCLUDED.description,
+                enabled = EXCLUDED.enabled,
+                rollout_percentage = EXCLUDED.rollout_percentage
+            RETURNING key, description, enabled, rollout_percentage
+            "#
+        )
+        .bind(&key)
+        .bind(payload.description)
+        .bind(payload.enabled)
+        .bind(payload.rollout_percentage)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(definition)
+    }.await;
+
+    match result {
+        Ok(definition) => {
+            invalidate_definitions(&state).await;
+            HandlerResponse::new(StatusCode::OK)
+                .message("Feature flag saved successfully")
+                .data(json!(definition))
+        }
+        Err(e) => {
+            tracing::error!("Saving feature flag {} failed: {}", key, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to save feature flag")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Deletes a global flag definition (tenant overrides cascade)
+pub async fn delete_flag(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> HandlerResponse {
+    let result: anyhow::Result<u64> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+
+        let deleted: u64 = sqlx::query("DELETE FROM feature_flags WHERE key = $1")
+            .bind(&key)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }.await;
+
+    match result {
+        Ok(0) => flag_not_found(),
+        Ok(_) => {
+            // Cached overrides for the deleted key are ignored once the definition is gone
+            invalidate_definitions(&state).await;
+            HandlerResponse::new(StatusCode::OK)
+                .message("Feature flag deleted successfully")
+                .data(json!({ "key": key }))
+        }
+        Err(e) => {
+            tracing::error!("Deleting feature flag {} failed: {}", key, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to delete feature flag")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Lists a tenant's overrides together with the resulting evaluation
+pub async fn list_tenant_overrides(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+) -> HandlerResponse {
+    match load_flags(&state, tenant_id).await {
+        Ok(flags) => {
+            let overrides: anyhow::Result<HashMap<String, bool>> = fetch_tenant_overrides(&state, tenant_id).await;
+            HandlerResponse::new(StatusCode::OK)
+                .message("Tenant feature flags retrieved successfully")
+                .data(json!({
+                    "tenant_id": tenant_id,
+                    "overrides": overrides.unwrap_or_default(),
+                    "flags": flags.evaluate_all(),
+                }))
+        }
+        Err(e) => {
+            tracing::error!("Listing feature flags for tenant {} failed: {}", tenant_id, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve tenant feature flags")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Forces a flag on or off for one tenant
+pub async fn set_tenant_override(
+    State(state): State<AppState>,
+    Path((tenant_id, key)): Path<(Uuid, String)>,
+    Json(payload): Json<TenantOverrideRequest>,
+) -> HandlerResponse {
+    let flag_key: String = key.clone();
+    let result: anyhow::Result<()> = state.database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        sqlx::query(
+            r#"
+            INSERT INTO tenant_feature_flags (tenant_id, flag_key, enabled)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tenant_id, flag_key) DO UPDATE SET enabled = EXCLUDED.enabled
+            "#
+        )
+        .bind(tenant_id)
+        .bind(flag_key)
+        .bind(payload.enabled)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    })).await;
+
+    match result {
+        Ok(()) => {
+            invalidate_overrides(&state, tenant_id).await;
+            HandlerResponse::new(StatusCode::OK)
+                .message("Tenant feature flag override saved successfully")
+                .data(json!({ "tenant_id": tenant_id, "key": key, "enabled": payload.enabled }))
+        }
+        // Foreign key violation: unknown tenant or flag
+        Err(e) if is_foreign_key_violation(&e) => HandlerResponse::new(StatusCode::NOT_FOUND)
+            .message("Tenant or feature flag not found")
+            .data(json!({ "error": "tenant_or_flag_not_found" })),
+        Err(e) => {
+            tracing::error!("Saving override {} for tenant {} failed: {}", key, tenant_id, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to save tenant feature flag override")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Removes a tenant override so the global rollout applies again
+pub async fn delete_tenant_override(
+    State(state): State<AppState>,
+    Path((tenant_id, key)): Path<(Uuid, String)>,
+) -> HandlerResponse {
+    let flag_key: String = key.clone();
+    let result: anyhow::Result<u64> = state.database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        let deleted: u64 = sqlx::query("DELETE FROM tenant_feature_flags WHERE flag_key = $1")
+            .bind(flag_key)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    })).await;
+
+    match result {
+        Ok(0) => flag_not_found(),
+        Ok(_) => {
+            invalidate_overrides(&state, tenant_id).await;
+            HandlerResponse::new(StatusCode::OK)
+                .message("Tenant feature flag override deleted successfully")
+                .data(json!({ "tenant_id": tenant_id, "key": key }))
+        }
+        Err(e) => {
+            tracing::error!("Deleting override {} for tenant {} failed: {}", key, tenant_id, e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to delete tenant feature flag override")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+// =============================================================================
+// LOADING
+// =============================================================================
+
+/// Builds the flag state of a tenant from cached definitions and overrides
+pub async fn load_flags(state: &AppState, tenant_id: Uuid) -> anyhow::Result<Flags> {
+    let definitions: Vec<FlagDefinition> = match state.cache.get_feature_flags().await {
+        Ok(Some(cached)) => cached,
+        other => {
+            if let Err(e) = other {
+                tracing::warn!("Failed to read feature flags from cache: {}", e);
+            }
+            let definitions: Vec<FlagDefinition> = fetch_flag_definitions(state).await?;
+            if let Err(e) = state.cache.set_feature_flags(&definitions).await {
+                tracing::warn!("Failed to cache feature flags: {}", e);
+            }
+            definitions
+        }
+    };
+
+    let overrides: HashMap<String, bool> = match state.cache.get_tenant_flag_overrides(&tenant_id).await {
+        Ok(Some(cached)) => cached,
+        other => {
+            if let Err(e) = other {
+                tracing::warn!("Failed to read flag overrides for tenant {} from cache: {}", tenant_id, e);
+            }
+            let overrides: HashMap<String, bool> = fetch_tenant_overrides(state, tenant_id).await?;
+            if let Err(e) = state.cache.set_tenant_flag_overrides(&tenant_id, &overrides).await {
+                tracing::warn!("Failed to cache flag overrides for tenant {}: {}", tenant_id, e);
+            }
+            overrides
+        }
+    };
+
+    Ok(Flags::new(tenant_id, definitions, overrides))
+}
+
+async fn fetch_flag_definitions(state: &AppState) -> anyhow::Result<Vec<FlagDefinition>> {
+    let pool: &sqlx::PgPool = state.database.get_pool()?;
+
+    let definitions: Vec<FlagDefinition> = sqlx::query_as(
+        "SELECT key, description, enabled, rollout_percentage FROM feature_flags ORDER BY key"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(definitions)
+}
+
+async fn fetch_tenant_overrides(state: &AppState, tenant_id: Uuid) -> anyhow::Result<HashMap<String, bool>> {
+    let rows: Vec<(String, bool)> = state.database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        sqlx::query_as("SELECT flag_key, enabled FROM tenant_feature_flags")
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+async fn invalidate_definitions(state: &AppState) {
+    if let Err(e) = state.cache.invalidate_feature_flags().await {
+        tracing::warn!("Failed to invalidate cached feature flags: {}", e);
+    }
+}
+
+async fn invalidate_overrides(state: &AppState, tenant_id: Uuid) {
+    if let Err(e) = state.cache.invalidate_tenant_flag_overrides(&tenant_id).await {
+        tracing::warn!("Failed to invalidate cached flag overrides for tenant {}: {}", tenant_id, e);
+    }
+}
+
+fn invalid_flag_key() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::BAD_REQUEST)
+        .message("Invalid feature flag key")
+        .data(json!({
+            "error": "invalid_flag_key",
+            "details": "key must be 1-100 characters of lowercase letters, digits, '_', '-' or '.'"
+        }))
+}
+
+fn flag_not_found() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::NOT_FOUND)
+        .message("Feature flag not found")
+        .data(json!({ "error": "flag_not_found" }))
+}
+
+/// Postgres error code 23503
+fn is_foreign_key_violation(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<sqlx::Error>(),
+        Some(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("23503")
+    )
+}
@@ -0,0 +1,5 @@
+// Feature flag module: admin management, tenant evaluation and the `Flags` extractor
+
+pub mod extractor;
+pub mod handler;
+pub mod routes;
@@ -0,0 +1,23 @@
+// Feature flag route definitions
+
+use axum::{routing::{get, put}, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Creates router with the tenant-facing feature flag endpoint
+pub fn tenant_feature_flag_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenant/feature-flags", get(handler::list_tenant_flags))
+}
+
+/// Creates router with admin endpoints for flag definitions and tenant overrides
+pub fn admin_feature_flag_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/feature-flags", get(handler::list_flags))
+        .route("/admin/feature-flags/{key}", put(handler::upsert_flag).delete(handler::delete_flag))
+        .route("/admin/tenants/{tenant_id}/feature-flags", get(handler::list_tenant_overrides))
+        .route(
+            "/admin/tenants/{tenant_id}/feature-flags/{key}",
+            put(handler::set_tenant_override).delete(handler::delete_tenant_override),
+        )
+}
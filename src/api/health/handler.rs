@@ -0,0 +1,40 @@
+// Liveness and readiness probe handlers
+
+use axum::{extract::State, http::StatusCode};
+use serde_json::json;
+
+use crate::config::state::AppState;
+use crate::core::startup::Dependency;
+use crate::utils::response_handler::HandlerResponse;
+
+/// The process is up and serving HTTP
+pub async fn live() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::OK)
+        .message("Service is alive")
+        .data(json!({ "status": "alive" }))
+}
+
+/// 200 once Postgres and the cache are initialized, 503 while they are still coming up.
+/// Includes the cache connection counters when the backend has a connection (Redis).
+pub async fn ready(State(state): State<AppState>) -> HandlerResponse {
+    let status = |dependency: Dependency| -> &'static str {
+        if state.readiness.is_ready(dependency) { "ready" } else { "starting" }
+    };
+    let mut data: serde_json::Value = json!({
+        "database": status(Dependency::Database),
+        "cache": status(Dependency::Cache),
+    });
+    if let Some(stats) = state.cache.connection_stats() {
+        data["cache_connection"] = json!(stats);
+    }
+
+    if state.readiness.all_ready() {
+        HandlerResponse::new(StatusCode::OK)
+            .message("Service is ready")
+            .data(data)
+    } else {
+        HandlerResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+            .message("Service is starting")
+            .data(data)
+    }
+}
@@ -0,0 +1,12 @@
+// Liveness and readiness probe routes
+
+use axum::{routing::get, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Creates router with the orchestrator probes (no tenant, no rate limit)
+pub fn health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(handler::live))
+        .route("/health/ready", get(handler::ready))
+}
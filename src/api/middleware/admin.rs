@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::json;
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+
+/// Header key for the admin API key
+pub const ADMIN_API_KEY_HEADER: &str = "x-admin-api-key";
+
+/// Middleware guarding `/admin` routes with the shared `ADMIN_API_KEY`
+pub async fn admin_auth_middleware(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, HandlerResponse> {
+    // 1. Admin API must be explicitly enabled
+    let expected: &str = state.environment.admin_api_key.as_deref().ok_or_else(|| {
+        HandlerResponse::new(StatusCode::FORBIDDEN)
+            .message("Admin API is disabled")
+            .data(json!({ "error": "admin_api_disabled" }))
+    })?;
+
+    // 2. Compare the provided key
+    let provided: &str = headers
+        .get(ADMIN_API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(HandlerResponse::new(StatusCode::UNAUTHORIZED)
+            .message("Invalid admin API key")
+            .data(json!({ "error": "invalid_admin_api_key" })));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Compares two byte strings without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0u8, |acc: u8, (x, y): (&u8, &u8)| acc | (x ^ y)) == 0
+}
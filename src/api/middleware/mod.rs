@@ -1,2 +1,8 @@
+pub mod admin;
+pub mod quota;
+pub mod rate_limit;
+pub mod read_session;
+pub mod readiness;
 pub mod tenant;
+pub mod tenant_tx;
 
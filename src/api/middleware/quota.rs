@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+use crate::api::middleware::tenant::{cache_unavailable, TenantContext};
+use crate::api::plans::handler::current_period;
+use crate::api::plans::quota::QuotaExceeded;
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+
+/// Middleware enforcing the tenant's monthly request quota (`max_requests_per_month`).
+/// Runs after the rate limiter, so requests it rejects are not counted. The counter is
+/// checked before the handler and incremented after it; requests rejected for being over
+/// the quota or unauthenticated (401) do not count. Concurrent requests can overshoot
+/// the limit by the number in flight.
+pub async fn monthly_quota_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(ctx) = request.extensions().get::<TenantContext>() else {
+        return next.run(request).await;
+    };
+    let (tenant_id, limit): (Uuid, Option<i64>) = (ctx.tenant_id, ctx.plan.max_requests_per_month);
+    let period: String = current_period();
+
+    // 1. Reject without counting once the quota is used up
+    if let Some(limit) = limit {
+        let used: i64 = match state.environment.cache_tenant_failure_policy
+            .read("monthly request counter", state.cache.get_monthly_requests(&tenant_id, &period).await)
+        {
+            Ok(used) => used,
+            Err(e) => {
+                tracing::error!("Cache unavailable for the request counter of tenant {}: {}", tenant_id, e);
+                return cache_unavailable().into_response();
+            }
+        };
+        if used >= limit {
+            let exceeded: QuotaExceeded = QuotaExceeded { quota: "max_requests_per_month", limit, current: used };
+            return HandlerResponse::from(exceeded).into_response();
+        }
+    }
+
+    // 2. Count the request once it was admitted
+    let response: Response = next.run(request).await;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        if let Err(e) = state.cache.incr_monthly_requests(&tenant_id, &period).await {
+            tracing::warn!("Failed to count a request of tenant {}: {}", tenant_id, e);
+        }
+    }
+    response
+}
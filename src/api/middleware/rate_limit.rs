@@ -0,0 +1,201 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::SocketAddr;
+use serde_json::json;
+use crate::api::auth::handler::authenticate;
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::core::rate_limiter::{RateLimit, RateLimitDecision};
+use crate::utils::response_handler::HandlerResponse;
+
+/// Route groups with their own per-caller limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteGroup {
+    /// `/auth/*`: credential endpoints, kept tight against brute force
+    Auth,
+    /// `/admin/*`
+    Admin,
+    Default,
+}
+
+impl RouteGroup {
+    fn from_path(path: &str) -> Self {
+        if path.starts_with("/auth/") {
+            RouteGroup::Auth
+        } else if path.starts_with("/admin/") {
+            RouteGroup::Admin
+        } else {
+            RouteGroup::Default
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Auth => "auth",
+            RouteGroup::Admin => "admin",
+            RouteGroup::Default => "default",
+        }
+    }
+}
+
+/// Middleware applying the per-tenant (plan), per-user and per-caller (route group) limits.
+/// Runs after the tenant middleware so the tenant plan is available when present.
+/// There are no API keys yet, so nothing is keyed on them.
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let env: &crate::config::environment::EnvironmentVariables = &state.environment;
+    if !env.rate_limit_enabled {
+        return next.run(request).await;
+    }
+
+    // 1. Collect the buckets this request counts against
+    let group: RouteGroup = RouteGroup::from_path(request.uri().path());
+    let caller_limit: u32 = match group {
+        RouteGroup::Auth => env.rate_limit_auth_per_minute,
+        RouteGroup::Admin | RouteGroup::Default => env.rate_limit_per_minute,
+    };
+
+    let mut buckets: Vec<(&'static str, String, RateLimit)> = Vec::new();
+
+    if let Some(ctx) = request.extensions().get::<TenantContext>() {
+        // NULL plan limit means unlimited
+        if let Some(per_minute) = ctx.plan.rate_limit_per_minute {
+            buckets.push(("tenant", format!("tenant:{}", ctx.tenant_id), RateLimit::per_minute(per_minute.max(1) as u32)));
+        }
+
+        // Only a session that validates keys a user bucket, so varying the token cannot
+        // buy fresh buckets; anything else is left to the handler and the caller bucket
+        if headers.contains_key(AUTHORIZATION) {
+            if let Ok(session) = authenticate(&state, ctx, &headers).await {
+                buckets.push(("user", format!("{}:user:{}", group.as_str(), session.user_id), RateLimit::per_minute(caller_limit)));
+            }
+        }
+    }
+
+    let caller: String = client_ip(&headers, &request, env.trust_forwarded_for);
+    buckets.push(("caller", format!("{}:{}", group.as_str(), caller), RateLimit::per_minute(caller_limit)));
+
+    // 2. Check every bucket; the most restrictive one decides the headers
+    let mut tightest: Option<(&'static str, RateLimitDecision)> = None;
+    for (scope, bucket, limit) in &buckets {
+        let decision: RateLimitDecision = state.rate_limiter.check(&state.cache, bucket, *limit).await;
+
+        let tighter: bool = match &tightest {
+            None => true,
+            Some((_, current)) if current.allowed != decision.allowed => !decision.allowed,
+            Some((_, current)) if !decision.allowed => decision.retry_after_ms > current.retry_after_ms,
+            Some((_, current)) => decision.remaining < current.remaining,
+        };
+        if tighter {
+            tightest = Some((scope, decision));
+        }
+    }
+
+    let (scope, decision) = match tightest {
+        Some(t) => t,
+        None => return next.run(request).await,
+    };
+
+    // 3. Reject or forward, adding RateLimit-* headers either way
+    let mut response: Response = if decision.allowed {
+        next.run(request).await
+    } else {
+        tracing::debug!("Rate limit exceeded for {} bucket", scope);
+        HandlerResponse::new(StatusCode::TOO_MANY_REQUESTS)
+            .message("Rate limit exceeded")
+            .data(json!({
+                "error": "rate_limited",
+                "scope": scope,
+                "retry_after_seconds": ceil_seconds(decision.retry_after_ms),
+            }))
+            .into_response()
+    };
+
+    apply_headers(response.headers_mut(), &decision);
+    response
+}
+
+/// Middleware applying the coarse per-IP limit (`RATE_LIMIT_IP_PER_MINUTE`). Runs before
+/// tenant resolution so unknown or random tenant IDs cannot reach Postgres unthrottled.
+/// Only rejected requests get RateLimit-* headers here; the inner limits set them otherwise.
+pub async fn ip_rate_limit_middleware(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let env: &crate::config::environment::EnvironmentVariables = &state.environment;
+    if !env.rate_limit_enabled {
+        return next.run(request).await;
+    }
+
+    let bucket: String = format!("global:{}", client_ip(&headers, &request, env.trust_forwarded_for));
+    let limit: RateLimit = RateLimit::per_minute(env.rate_limit_ip_per_minute);
+    let decision: RateLimitDecision = state.rate_limiter.check(&state.cache, &bucket, limit).await;
+    if decision.allowed {
+        return next.run(request).await;
+    }
+
+    tracing::debug!("Rate limit exceeded for ip bucket");
+    let mut response: Response = HandlerResponse::new(StatusCode::TOO_MANY_REQUESTS)
+        .message("Rate limit exceeded")
+        .data(json!({
+            "error": "rate_limited",
+            "scope": "ip",
+            "retry_after_seconds": ceil_seconds(decision.retry_after_ms),
+        }))
+        .into_response();
+    apply_headers(response.headers_mut(), &decision);
+    response
+}
+
+/// Identifies the caller by client IP. Unvalidated credentials are never used as a key, as
+/// a caller could pick a fresh bucket per request by varying the header; validated
+/// sessions get their own bucket in `rate_limit_middleware`.
+pub(crate) fn client_ip(headers: &HeaderMap, request: &Request, trust_forwarded_for: bool) -> String {
+    // X-Forwarded-For is client-controlled unless a trusted proxy overwrites it
+    if trust_forwarded_for {
+        let forwarded: Option<&str> = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        if let Some(ip) = forwarded {
+            return format!("ip:{}", ip);
+        }
+    }
+
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Sets the IETF RateLimit-* headers (and Retry-After when rejected)
+fn apply_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    let reset_seconds: i64 = ceil_seconds(decision.reset_after_ms);
+
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(reset_seconds));
+    if let Ok(policy) = HeaderValue::from_str(&format!("{};w=60", decision.limit)) {
+        headers.insert("ratelimit-policy", policy);
+    }
+
+    if !decision.allowed {
+        headers.insert("retry-after", HeaderValue::from(ceil_seconds(decision.retry_after_ms).max(1)));
+    }
+}
+
+fn ceil_seconds(ms: i64) -> i64 {
+    (ms + 999) / 1000
+}
@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::api::auth::handler::authenticate;
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::database::Session;
+
+/// Caller identity for read-your-writes routing (`DatabaseService::with_tenant_read`):
+/// tenant plus the user of a valid bearer session. Anonymous requests have no key, so
+/// their reads are never pinned to the primary.
+#[derive(Debug, Clone)]
+pub struct ReadSession(Option<String>);
+
+impl ReadSession {
+    pub fn key(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+/// Middleware attaching a `ReadSession` to tenant requests and, after a successful
+/// write (any method other than GET/HEAD/OPTIONS/TRACE), pinning the caller's reads
+/// to the primary for `DB_READ_YOUR_WRITES_MS`.
+///
+/// The window is tracked by the instance that served the write; see `ReplicaSet`.
+pub async fn read_session_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(ctx) = request.extensions().get::<TenantContext>().cloned() else {
+        return next.run(request).await;
+    };
+
+    // Only sessions that validate key the window; a missing, invalid or unreadable token
+    // leaves the request anonymous and the handler decides whether that is acceptable
+    let user: Option<Session> = match state.database.replicas() {
+        Some(_) => authenticate(&state, &ctx, request.headers()).await.ok(),
+        None => None,
+    };
+    let session: ReadSession = ReadSession(user.map(|user: Session| format!("{}:{}", ctx.tenant_id, user.user_id)));
+    let is_write: bool = !request.method().is_safe();
+    request.extensions_mut().insert(session.clone());
+
+    let response: Response = next.run(request).await;
+
+    if let Some(key) = session.key() {
+        if is_write && (response.status().is_success() || response.status().is_redirection()) {
+            state.database.record_write(key);
+        }
+    }
+    response
+}
@@ -0,0 +1,29 @@
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+
+/// Middleware answering 503 until the database and the cache are initialized, for
+/// requests that reach the instance early (`STARTUP_SERVE_BEFORE_READY`)
+pub async fn readiness_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if state.readiness.all_ready() {
+        return next.run(request).await;
+    }
+
+    let mut response: Response = HandlerResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+        .message("Service is starting, try again shortly")
+        .data(json!({ "error": "not_ready" }))
+        .into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from_static("5"));
+    response
+}
@@ -1,12 +1,18 @@
 use axum::{
     extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{header::HOST, HeaderMap, StatusCode},
     middleware::Next,
     response::Response,
 };
+use std::sync::Arc;
 use uuid::Uuid;
+use crate::api::settings::handler::load_tenant_settings;
+use crate::config::tenant_settings::{TenantSettings, VersionedSettings};
 use crate::utils::response_handler::HandlerResponse;
 use crate::config::state::AppState;
+use crate::api::plans::handler::load_tenant_plan;
+use crate::api::plans::quota::PlanLimits;
+use crate::database::CachedDomain;
 use serde_json::json;
 
 /// Header key for Tenant ID
@@ -16,35 +22,50 @@ pub const TENANT_ID_HEADER: &str = "x-tenant-id";
 #[derive(Debug, Clone)]
 pub struct TenantContext {
     pub tenant_id: Uuid,
+    /// Tenant settings, loaded once per request so handlers need no extra queries
+    pub settings: Arc<TenantSettings>,
+    /// Version of `settings` (0 when the tenant still uses the defaults)
+    pub settings_version: i32,
+    /// Plan quotas, used for rate limiting and usage checks
+    pub plan: Arc<PlanLimits>,
 }
 
-/// Middleware to extract Tenant ID from header and set up context
+/// Middleware to extract Tenant ID from header (or a verified custom domain) and set up context
 pub async fn tenant_context_middleware(
     State(state): State<AppState>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
 ) -> Result<Response, HandlerResponse> {
-    // 1. Extract Tenant ID from Header
-    let tenant_id_str: &str = headers
-        .get(TENANT_ID_HEADER)
-        .and_then(|value| value.to_str().ok())
-        .ok_or_else(|| {
+    // 1. Resolve Tenant ID from header, falling back to a verified custom domain
+    let tenant_id: Uuid = match headers.get(TENANT_ID_HEADER) {
+        Some(value) => {
+            // 2. Parse UUID
+            value
+                .to_str()
+                .ok()
+                .and_then(|s: &str| Uuid::parse_str(s).ok())
+                .ok_or_else(|| {
+                    HandlerResponse::new(StatusCode::BAD_REQUEST)
+                        .message("Invalid Tenant ID format")
+                        .data(json!({ "error": "invalid_tenant_id" }))
+                })?
+        }
+        None => resolve_tenant_from_host(&state, &headers).await?.ok_or_else(|| {
             HandlerResponse::new(StatusCode::UNAUTHORIZED)
                 .message("Missing Tenant ID header")
                 .data(json!({ "error": "missing_tenant_id" }))
-        })?;
+        })?,
+    };
 
-    // 2. Parse UUID
-    let tenant_id: Uuid = Uuid::parse_str(tenant_id_str).map_err(|_| {
-        HandlerResponse::new(StatusCode::BAD_REQUEST)
-            .message("Invalid Tenant ID format")
-            .data(json!({ "error": "invalid_tenant_id" }))
-    })?;
-
-    // 3. Validate against Cache/Redis (Optimization)
-    // Check Redis first (Hot Path)
-    let is_cached: bool = state.redis.tenant_exists(&tenant_id).await.unwrap_or(false);
+    // 3. Validate against the cache (Optimization)
+    // Check the cache first (Hot Path); when it is down, CACHE_TENANT_FAILURE_POLICY decides
+    let is_cached: bool = state.environment.cache_tenant_failure_policy
+        .read("tenant validation", state.cache.tenant_exists(&tenant_id).await)
+        .map_err(|e| {
+            tracing::error!("Cache unavailable for tenant validation: {}", e);
+            cache_unavailable()
+        })?;
 
     if !is_cached {
         // Not in cache, check Database
@@ -72,21 +93,133 @@ pub async fn tenant_context_middleware(
                 .data(json!({ "error": "tenant_not_found" })));
         }
 
-        // Valid tenant, populate Redis
-        if let Err(e) = state.redis.set_tenant(&tenant_id).await {
+        // Valid tenant, populate the cache
+        if let Err(e) = state.cache.set_tenant(&tenant_id).await {
             // Don't fail request if cache fails, just log it
             tracing::warn!("Failed to cache tenant {}: {}", tenant_id, e);
         }
         
         tracing::debug!("Tenant {} validated via DB and cached", tenant_id);
     } else {
-        tracing::debug!("Tenant {} validated via Redis cache", tenant_id);
+        tracing::debug!("Tenant {} validated via cache", tenant_id);
     }
 
-    // 4. Store in Request Extensions
-    // This makes the tenant_id available to subsequent middleware and handlers
-    request.extensions_mut().insert(TenantContext { tenant_id });
+    // 4. Load Tenant Settings and Plan (cache first, then Database)
+    let VersionedSettings { settings, version } = load_tenant_settings(&state, tenant_id).await.map_err(|e| {
+        tracing::error!("Failed to load settings for tenant {}: {}", tenant_id, e);
+        HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .message("Internal Service Error")
+    })?;
+
+    let plan: PlanLimits = load_tenant_plan(&state, tenant_id).await.map_err(|e| {
+        tracing::error!("Failed to load plan for tenant {}: {}", tenant_id, e);
+        HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .message("Internal Service Error")
+    })?;
+
+    // 5. Store in Request Extensions
+    // This makes the tenant context available to subsequent middleware and handlers
+    request.extensions_mut().insert(TenantContext {
+        tenant_id,
+        settings: Arc::new(settings),
+        settings_version: version,
+        plan: Arc::new(plan),
+    });
 
-    // 5. Proceed
+    // 6. Proceed
     Ok(next.run(request).await)
 }
+
+/// Resolves the tenant owning the request's `Host` header through verified custom domains.
+/// The cache is consulted first; unknown hostnames are cached as well to spare the database.
+async fn resolve_tenant_from_host(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<Uuid>, HandlerResponse> {
+    let hostname: String = match headers
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .and_then(normalize_host)
+    {
+        Some(h) => h,
+        None => return Ok(None),
+    };
+
+    // Check the cache first (Hot Path); when it is down, CACHE_TENANT_FAILURE_POLICY decides
+    let cached: Option<CachedDomain> = state.environment.cache_tenant_failure_policy
+        .read("custom domain resolution", state.cache.get_domain_tenant(&hostname).await)
+        .map_err(|e| {
+            tracing::error!("Cache unavailable for domain {}: {}", hostname, e);
+            cache_unavailable()
+        })?;
+    match cached {
+        Some(CachedDomain::Tenant(tenant_id)) => {
+            tracing::debug!("Domain {} resolved to tenant {} via cache", hostname, tenant_id);
+            return Ok(Some(tenant_id));
+        }
+        Some(CachedDomain::Unknown) => return Ok(None),
+        None => {}
+    }
+
+    let tenant_id: Option<Uuid> = state.database.resolve_tenant_domain(&hostname).await.map_err(|e| {
+        tracing::error!("Domain resolution error: {}", e);
+        HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .message("Internal Service Error")
+    })?;
+
+    let resolution: CachedDomain = match tenant_id {
+        Some(id) => CachedDomain::Tenant(id),
+        None => CachedDomain::Unknown,
+    };
+
+    if let Err(e) = state.cache.set_domain_tenant(&hostname, resolution).await {
+        // Don't fail request if cache fails, just log it
+        tracing::warn!("Failed to cache domain {}: {}", hostname, e);
+    }
+
+    Ok(tenant_id)
+}
+
+/// 503 for requests a fail-closed use case cannot serve while the cache is down
+pub fn cache_unavailable() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+        .message("Cache unavailable, try again shortly")
+        .data(json!({ "error": "cache_unavailable" }))
+}
+
+/// Normalizes a `Host` header value into a bare lowercase hostname.
+/// Strips the port and trailing dot; IP literals never map to a tenant.
+pub fn normalize_host(host: &str) -> Option<String> {
+    let host: &str = host.trim();
+    if host.is_empty() || host.starts_with('[') {
+        return None;
+    }
+
+    let without_port: &str = host.rsplit_once(':').map_or(host, |(name, _port)| name);
+    let hostname: String = without_port.trim_end_matches('.').to_ascii_lowercase();
+
+    if hostname.is_empty() || hostname.parse::<std::net::Ipv4Addr>().is_ok() {
+        return None;
+    }
+
+    Some(hostname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_are_lowercased_without_port_or_trailing_dot() {
+        assert_eq!(normalize_host("App.Example.COM:8443").as_deref(), Some("app.example.com"));
+        assert_eq!(normalize_host(" app.example.com. ").as_deref(), Some("app.example.com"));
+        assert_eq!(normalize_host("localhost").as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn ip_literals_and_empty_hosts_map_to_no_tenant() {
+        for host in ["", "  ", "127.0.0.1", "10.0.0.1:3000", "[::1]:3000", "[2001:db8::1]", ":8080"] {
+            assert_eq!(normalize_host(host), None, "{:?}", host);
+        }
+    }
+}
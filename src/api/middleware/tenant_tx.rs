@@ -0,0 +1,146 @@
+use std::sync::{Arc, Mutex};
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use sqlx::PgConnection;
+
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::database::{DatabaseService, TenantTx, TxOptions, TxTimeouts};
+use crate::utils::response_handler::HandlerResponse;
+
+/// Per-request home of the tenant transaction, shared between the extractor and the middleware
+#[derive(Clone, Default)]
+struct TxSlot(Arc<Mutex<SlotState>>);
+
+#[derive(Default)]
+enum SlotState {
+    /// No handler asked for a transaction yet
+    #[default]
+    Unused,
+    /// Held by a `RequestTx` extractor, which may not have begun the transaction yet
+    Taken,
+    /// Returned by the extractor, waiting for the response
+    Ready(TenantTx),
+}
+
+impl TxSlot {
+    fn lock(&self) -> std::sync::MutexGuard<'_, SlotState> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Middleware owning the request's tenant transaction: commits it when the
+/// response is successful (2xx/3xx) and rolls it back otherwise.
+/// Requests whose handler never extracts `RequestTx` do not touch the database.
+pub async fn tenant_tx_middleware(mut request: Request, next: Next) -> Response {
+    let slot: TxSlot = TxSlot::default();
+    request.extensions_mut().insert(slot.clone());
+
+    let response: Response = next.run(request).await;
+
+    let state: SlotState = std::mem::take(&mut *slot.lock());
+    let tx: TenantTx = match state {
+        SlotState::Ready(tx) => tx,
+        SlotState::Unused => return response,
+        SlotState::Taken => {
+            // The extractor outlived the handler (e.g. moved into a task); the drop rolls it back
+            tracing::warn!("Tenant transaction was not released by the handler, rolled back");
+            return response;
+        }
+    };
+
+    if !(response.status().is_success() || response.status().is_redirection()) {
+        if let Err(e) = tx.rollback().await {
+            tracing::warn!("Failed to roll back tenant transaction: {}", e);
+        }
+        return response;
+    }
+
+    match tx.commit().await {
+        Ok(()) => response,
+        Err(e) => {
+            tracing::error!("Failed to commit tenant transaction: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to commit changes")
+                .data(json!({ "error": "commit_failed" }))
+                .into_response()
+        }
+    }
+}
+
+/// Extractor handing the handler a transaction bound to the request's tenant.
+///
+/// The transaction begins on the first `conn()` call, so no pooled connection is held
+/// while later extractors (e.g. `Json`) read and validate the body, or for requests the
+/// handler rejects before touching the database. It is committed by
+/// `tenant_tx_middleware` if the handler responds with success and rolled back
+/// otherwise, so handlers neither commit nor roll back themselves.
+/// Requires `tenant_context_middleware` and `tenant_tx_middleware` on the route;
+/// a `TxTimeouts` extension on the route overrides the configured timeouts.
+pub struct RequestTx {
+    database: DatabaseService,
+    tenant_id: uuid::Uuid,
+    timeouts: TxTimeouts,
+    tx: Option<TenantTx>,
+    slot: TxSlot,
+}
+
+impl RequestTx {
+    pub fn tenant_id(&self) -> uuid::Uuid {
+        self.tenant_id
+    }
+
+    /// The transaction's connection, beginning the transaction on first use
+    /// (`.fetch_one(tx.conn().await?)`)
+    pub async fn conn(&mut self) -> anyhow::Result<&mut PgConnection> {
+        let tx: &mut TenantTx = match &mut self.tx {
+            Some(tx) => tx,
+            tx @ None => tx.insert(
+                self.database.begin_tenant_with(self.tenant_id, TxOptions::new().timeouts(self.timeouts)).await?
+            ),
+        };
+        Ok(tx)
+    }
+}
+
+impl FromRequestParts<AppState> for RequestTx {
+    type Rejection = HandlerResponse;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let (Some(ctx), Some(slot)) = (parts.extensions.get::<TenantContext>(), parts.extensions.get::<TxSlot>()) else {
+            tracing::error!("RequestTx used on a route without tenant_context_middleware and tenant_tx_middleware");
+            return Err(HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Internal Service Error"));
+        };
+        let (tenant_id, slot): (uuid::Uuid, TxSlot) = (ctx.tenant_id, slot.clone());
+        let timeouts: TxTimeouts = parts.extensions.get::<TxTimeouts>().copied().unwrap_or_default();
+
+        let previous: SlotState = std::mem::replace(&mut *slot.lock(), SlotState::Taken);
+        let tx: Option<TenantTx> = match previous {
+            SlotState::Unused => None,
+            SlotState::Taken => {
+                tracing::error!("RequestTx extracted twice in one request");
+                return Err(HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                    .message("Internal Service Error"));
+            }
+            SlotState::Ready(tx) => Some(tx),
+        };
+
+        Ok(RequestTx { database: state.database.clone(), tenant_id, timeouts, tx, slot })
+    }
+}
+
+impl Drop for RequestTx {
+    /// Hands the transaction back to the middleware, which decides on commit or rollback
+    fn drop(&mut self) {
+        *self.slot.lock() = match self.tx.take() {
+            Some(tx) => SlotState::Ready(tx),
+            None => SlotState::Unused,
+        };
+    }
+}
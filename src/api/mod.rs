@@ -1,3 +1,9 @@
 // API module exports
 pub mod middleware;
 pub mod auth;
+pub mod domains;
+pub mod feature_flags;
+pub mod health;
+pub mod offboarding;
+pub mod plans;
+pub mod settings;
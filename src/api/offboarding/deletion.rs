@@ -0,0 +1,106 @@
+// Tenant hard deletion
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::config::state::AppState;
+use crate::database::tenant_tables::TENANT_TABLES;
+use super::jobs::JobOutput;
+
+/// Permanently deletes a tenant and everything it owns.
+///
+/// Rows are removed in reverse registry order (children before parents) in a
+/// single transaction together with the tenant row and its tombstone, so the
+/// database never holds a half-deleted tenant. A tenant table missing from the
+/// registry makes the final `DELETE FROM tenants` fail on its foreign key and
+/// rolls everything back. Cached keys and sessions are purged after commit.
+pub async fn hard_delete_tenant(state: &AppState, tenant_id: Uuid, job_id: Uuid, reason: Option<String>) -> Result<JobOutput> {
+    let pool: &sqlx::PgPool = state.database.get_pool()?;
+    let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = pool.begin().await.context("Failed to begin transaction")?;
+
+    // 1. Lock the tenant so no concurrent job deletes or exports it halfway
+    let tenant: Option<(String, Option<DateTime<Utc>>)> = sqlx::query_as(
+        r#"
+        SELECT p.code, t.created_at
+        FROM tenants t
+        JOIN plans p ON p.id = t.plan_id
+        WHERE t.id = $1
+        FOR UPDATE OF t
+        "#
+    )
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (plan_code, tenant_created_at) = tenant.context("Tenant not found")?;
+
+    sqlx::query("SELECT set_config('app.current_tenant_id', $1, true)")
+        .bind(tenant_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to set tenant context")?;
+
+    // Needed afterwards to evict the domain cache
+    let hostnames: Vec<String> = sqlx::query_scalar("SELECT hostname FROM tenant_domains WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    // 2. Delete tenant rows, children first
+    let mut row_counts: Map<String, Value> = Map::new();
+    for table in TENANT_TABLES.iter().rev() {
+        // Table names come from the static registry, never from input
+        let deleted: u64 = sqlx::query(&format!("DELETE FROM {} WHERE tenant_id = $1", table.name))
+            .bind(tenant_id)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to delete rows from {}", table.name))?
+            .rows_affected();
+        row_counts.insert(table.name.to_string(), json!(deleted));
+    }
+
+    sqlx::query("DELETE FROM tenants WHERE id = $1")
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete tenant")?;
+
+    // 3. Tombstone for audit
+    sqlx::query(
+        r#"
+        INSERT INTO tenant_tombstones (tenant_id, plan_code, row_counts, reason, deleted_by_job_id, tenant_created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#
+    )
+    .bind(tenant_id)
+    .bind(&plan_code)
+    .bind(Value::Object(row_counts.clone()))
+    .bind(&reason)
+    .bind(job_id)
+    .bind(tenant_created_at)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to record tenant tombstone")?;
+
+    tx.commit().await.context("Failed to commit transaction")?;
+    tracing::info!("Hard-deleted tenant {}", tenant_id);
+
+    // 4. Purge cache and sessions; the data is already gone, so only report failures
+    let purged_keys: Option<u64> = match state.cache.purge_tenant(&tenant_id, &hostnames).await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            tracing::warn!("Failed to purge cached keys for deleted tenant {}: {}", tenant_id, e);
+            None
+        }
+    };
+
+    Ok(JobOutput {
+        artifact_path: None,
+        result: json!({
+            "deleted_rows": row_counts,
+            "cache_purged": purged_keys.is_some(),
+            "purged_keys": purged_keys,
+        }),
+    })
+}
@@ -0,0 +1,301 @@
+// Tenant data export into a versioned zip archive of JSON-lines files
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use tokio::sync::mpsc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::database::tenant_tables::{TenantTable, TENANT_TABLES};
+use crate::database::DatabaseService;
+use super::jobs::JobOutput;
+
+/// Bumped whenever the archive layout changes, so importers can reject unknown formats
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Identifies archives produced by this exporter
+pub const EXPORT_FORMAT_NAME: &str = "tenant-export";
+
+/// Rows per chunk handed to the archive writer
+const ROWS_PER_CHUNK: usize = 500;
+
+/// Chunks buffered between the database reader and the archive writer
+const ARCHIVE_CHANNEL_CAPACITY: usize = 16;
+
+/// Path of the archive of an export job; it is written as `<path>.partial` first
+pub fn archive_path(export_dir: &str, tenant_id: Uuid, job_id: Uuid) -> PathBuf {
+    Path::new(export_dir).join(format!("tenant-{}-{}.zip", tenant_id, job_id))
+}
+
+/// Exports every tenant-scoped table of a tenant to `<export_dir>/tenant-<id>-<job>.zip`.
+///
+/// Archive layout:
+/// - `manifest.json`: format name and version, tenant id, export time, row counts
+/// - `tenant.json`: the tenant row (with its plan code)
+/// - `tables/<table>.jsonl`: one JSON object per row, in registry order
+///
+/// All reads happen in one read-only REPEATABLE READ transaction, so the
+/// archive is a consistent snapshot even while the tenant keeps writing.
+/// File and zip writes run on a blocking thread fed through a channel; a failed
+/// export leaves no file behind.
+pub async fn export_tenant(database: &DatabaseService, export_dir: &str, tenant_id: Uuid, job_id: Uuid) -> Result<JobOutput> {
+    // 1. Consistent snapshot, scoped to the tenant
+    let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = begin_snapshot(database, tenant_id).await?;
+    let tenant: Value = fetch_tenant_row(&mut tx, tenant_id).await?;
+
+    // 2. Write the archive under a temporary name
+    let final_path: PathBuf = archive_path(export_dir, tenant_id, job_id);
+    let (sender, receiver) = mpsc::channel::<ArchiveOp>(ARCHIVE_CHANNEL_CAPACITY);
+    let writer_path: PathBuf = final_path.clone();
+    let writer: tokio::task::JoinHandle<Result<Option<u64>>> =
+        tokio::task::spawn_blocking(move || write_archive(&writer_path, receiver));
+
+    let read: Result<Value> = send_entries(&mut tx, tenant_id, tenant, &sender).await;
+    drop(sender);
+    let written: Result<Option<u64>> = writer.await.unwrap_or_else(|e| Err(e.into()));
+
+    // The writer's error explains why the reader could not hand it more rows
+    let (manifest, size_bytes): (Value, u64) = match (read, written) {
+        (_, Err(e)) => return Err(e),
+        (Err(e), Ok(_)) => return Err(e),
+        (Ok(manifest), Ok(Some(size_bytes))) => (manifest, size_bytes),
+        (Ok(_), Ok(None)) => anyhow::bail!("Archive writer stopped before the export finished"),
+    };
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    tracing::info!("Exported tenant {} to {}", tenant_id, final_path.display());
+
+    Ok(JobOutput {
+        artifact_path: Some(final_path.to_string_lossy().into_owned()),
+        result: json!({
+            "format_version": EXPORT_FORMAT_VERSION,
+            "tables": manifest["tables"],
+            "size_bytes": size_bytes,
+        }),
+    })
+}
+
+/// Archive writes, handed from the database reader to the blocking writer
+enum ArchiveOp {
+    /// Starts a new entry
+    File(String),
+    /// Appends to the current entry
+    Data(Vec<u8>),
+    /// Everything was sent: finish and publish the archive
+    Finish,
+}
+
+/// Reads the snapshot and sends it to the writer; returns the manifest
+async fn send_entries(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    tenant_id: Uuid,
+    tenant: Value,
+    sender: &mpsc::Sender<ArchiveOp>,
+) -> Result<Value> {
+    let send = |op: ArchiveOp| async move {
+        sender.send(op).await.map_err(|_| anyhow::anyhow!("Archive writer stopped"))
+    };
+
+    let mut tables: Vec<Value> = Vec::with_capacity(TENANT_TABLES.len());
+    for table in TENANT_TABLES {
+        let file_name: String = format!("tables/{}.jsonl", table.name);
+        send(ArchiveOp::File(file_name.clone())).await?;
+
+        let rows: u64 = send_table_rows(tx, table, tenant_id, sender).await
+            .with_context(|| format!("Failed to export table {}", table.name))?;
+        tables.push(json!({ "name": table.name, "file": file_name, "rows": rows }));
+    }
+
+    send(ArchiveOp::File("tenant.json".into())).await?;
+    send(ArchiveOp::Data(serde_json::to_vec_pretty(&tenant)?)).await?;
+
+    let manifest: Value = json!({
+        "format": EXPORT_FORMAT_NAME,
+        "version": EXPORT_FORMAT_VERSION,
+        "tenant_id": tenant_id,
+        "exported_at": Utc::now().to_rfc3339(),
+        "tables": tables,
+    });
+    send(ArchiveOp::File("manifest.json".into())).await?;
+    send(ArchiveOp::Data(serde_json::to_vec_pretty(&manifest)?)).await?;
+    send(ArchiveOp::Finish).await?;
+
+    Ok(manifest)
+}
+
+/// Writes the archive to `<final_path>.partial` and renames it once `Finish` arrives.
+/// Runs on a blocking thread. Returns the archive size, or None when the reader gave up
+/// first; the partial file is removed unless the archive was published.
+fn write_archive(final_path: &Path, mut receiver: mpsc::Receiver<ArchiveOp>) -> Result<Option<u64>> {
+    let partial_path: PathBuf = final_path.with_extension("zip.partial");
+    let written: Result<Option<u64>> = (|| {
+        if let Some(dir) = final_path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create export directory {}", dir.display()))?;
+        }
+        let file: File = File::create(&partial_path)
+            .with_context(|| format!("Failed to create {}", partial_path.display()))?;
+        let mut zip: ZipWriter<BufWriter<File>> = ZipWriter::new(BufWriter::new(file));
+        let options: SimpleFileOptions = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        loop {
+            match receiver.blocking_recv() {
+                Some(ArchiveOp::File(name)) => zip.start_file(name, options)?,
+                Some(ArchiveOp::Data(bytes)) => zip.write_all(&bytes)?,
+                Some(ArchiveOp::Finish) => break,
+                None => return Ok(None),
+            }
+        }
+
+        zip.finish()?.flush()?;
+
+        // Publish the finished archive
+        std::fs::rename(&partial_path, final_path)
+            .with_context(|| format!("Failed to move archive to {}", final_path.display()))?;
+        Ok(Some(std::fs::metadata(final_path)?.len()))
+    })();
+
+    if !matches!(written, Ok(Some(_))) {
+        if let Err(e) = std::fs::remove_file(&partial_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove {}: {}", partial_path.display(), e);
+            }
+        }
+    }
+    written
+}
+
+/// Opens a read-only REPEATABLE READ transaction scoped to a tenant.
+/// Queries still filter on tenant_id: RLS does not apply to superusers or the
+/// table owner, and an export must never leak another tenant's rows.
+pub(super) async fn begin_snapshot(database: &DatabaseService, tenant_id: Uuid) -> Result<sqlx::Transaction<'static, sqlx::Postgres>> {
+    let pool: &sqlx::PgPool = database.get_pool()?;
+    let mut tx: sqlx::Transaction<'static, sqlx::Postgres> = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("SELECT set_config('app.current_tenant_id', $1, true)")
+        .bind(tenant_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to set tenant context")?;
+
+    Ok(tx)
+}
+
+/// Reads the tenant row as JSON, with its plan code (plan ids differ between environments)
+pub(super) async fn fetch_tenant_row(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, tenant_id: Uuid) -> Result<Value> {
+    sqlx::query_scalar(
+        r#"
+        SELECT to_jsonb(t) || jsonb_build_object('plan_code', p.code)
+        FROM tenants t
+        JOIN plans p ON p.id = t.plan_id
+        WHERE t.id = $1
+        "#
+    )
+    .bind(tenant_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .context("Tenant not found")
+}
+
+/// Streams one table into the current archive entry, one JSON document per line
+async fn send_table_rows(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &TenantTable,
+    tenant_id: Uuid,
+    sender: &mpsc::Sender<ArchiveOp>,
+) -> Result<u64> {
+    // Table names come from the static registry, never from input
+    let query: String = format!(
+        "SELECT to_jsonb(t)::text FROM {} t WHERE tenant_id = $1 ORDER BY {}",
+        table.name, table.order_by
+    );
+
+    let mut rows = sqlx::query_scalar::<_, String>(&query).bind(tenant_id).fetch(&mut **tx);
+    let mut count: u64 = 0;
+    let mut chunk: Vec<u8> = Vec::new();
+
+    while let Some(row) = rows.try_next().await? {
+        chunk.extend_from_slice(row.as_bytes());
+        chunk.push(b'\n');
+        count += 1;
+
+        if count % ROWS_PER_CHUNK as u64 == 0 {
+            sender.send(ArchiveOp::Data(std::mem::take(&mut chunk))).await
+                .map_err(|_| anyhow::anyhow!("Archive writer stopped"))?;
+        }
+    }
+    if !chunk.is_empty() {
+        sender.send(ArchiveOp::Data(chunk)).await
+            .map_err(|_| anyhow::anyhow!("Archive writer stopped"))?;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_archive() -> PathBuf {
+        archive_path(&std::env::temp_dir().join(format!("export-test-{}", Uuid::new_v4())).to_string_lossy(), Uuid::nil(), Uuid::nil())
+    }
+
+    fn channel(ops: Vec<ArchiveOp>) -> mpsc::Receiver<ArchiveOp> {
+        let (sender, receiver) = mpsc::channel::<ArchiveOp>(ops.len().max(1));
+        for op in ops {
+            sender.blocking_send(op).unwrap();
+        }
+        receiver
+    }
+
+    #[test]
+    fn finished_archives_are_published() {
+        let path: PathBuf = scratch_archive();
+        let receiver = channel(vec![
+            ArchiveOp::File("tables/users.jsonl".into()),
+            ArchiveOp::Data(b"{\"id\":1}\n".to_vec()),
+            ArchiveOp::Data(b"{\"id\":2}\n".to_vec()),
+            ArchiveOp::Finish,
+        ]);
+
+        let size: Option<u64> = write_archive(&path, receiver).unwrap();
+        assert_eq!(size, Some(std::fs::metadata(&path).unwrap().len()));
+        assert!(!path.with_extension("zip.partial").exists());
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut contents: String = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("tables/users.jsonl").unwrap(), &mut contents).unwrap();
+        assert_eq!(contents, "{\"id\":1}\n{\"id\":2}\n");
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn abandoned_archives_leave_no_file() {
+        let path: PathBuf = scratch_archive();
+        let receiver = channel(vec![ArchiveOp::File("tenant.json".into()), ArchiveOp::Data(b"{}".to_vec())]);
+
+        assert_eq!(write_archive(&path, receiver).unwrap(), None);
+        assert!(!path.exists() && !path.with_extension("zip.partial").exists());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn archives_are_named_after_tenant_and_job() {
+        let tenant_id: Uuid = Uuid::from_u128(1);
+        let job_id: Uuid = Uuid::from_u128(2);
+        assert_eq!(
+            archive_path("exports", tenant_id, job_id),
+            Path::new("exports").join(format!("tenant-{}-{}.zip", tenant_id, job_id))
+        );
+    }
+}
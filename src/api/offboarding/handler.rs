@@ -0,0 +1,305 @@
+// Tenant data lifecycle handlers (admin): export, import, clone, deletion
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio_util::io::ReaderStream;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::config::state::AppState;
+use crate::utils::response_handler::{HandlerResponse, RawBody};
+use super::deletion::hard_delete_tenant;
+use super::export::export_tenant;
+use super::import::{import_snapshot, read_archive, read_live_tenant, ImportOptions, TenantSnapshot};
+use super::jobs::{create_job, find_job, spawn_job, JobKind, JobStatus, TenantJob};
+
+// =============================================================================
+// DTOs
+// =============================================================================
+
+#[derive(Deserialize)]
+pub struct DeleteTenantRequest {
+    /// Must repeat the tenant id from the path, guarding against deleting the wrong tenant
+    pub confirm_tenant_id: Uuid,
+    /// Recorded on the tombstone
+    pub reason: Option<String>,
+}
+
+// =============================================================================
+// HANDLERS
+// =============================================================================
+
+/// Starts an export job for a tenant
+pub async fn start_export(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+) -> HandlerResponse {
+    let result: anyhow::Result<Option<TenantJob>> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+        if !tenant_exists(pool, tenant_id).await? {
+            return Ok(None);
+        }
+
+        let job: TenantJob = create_job(pool, tenant_id, JobKind::Export).await?;
+
+        let database = state.database.clone();
+        let export_dir: String = state.environment.export_dir.to_string();
+        let job_id: Uuid = job.id;
+        spawn_job(pool.clone(), job_id, async move {
+            export_tenant(&database, &export_dir, tenant_id, job_id).await
+        });
+
+        Ok(Some(job))
+    }.await;
+
+    match result {
+        Ok(Some(job)) => HandlerResponse::new(StatusCode::ACCEPTED)
+            .message("Export started")
+            .data(job.to_json()),
+        Ok(None) => tenant_not_found(),
+        Err(e) => {
+            tracing::error!("Starting tenant export failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to start export")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Starts a hard-delete job for a tenant
+pub async fn delete_tenant(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Json(payload): Json<DeleteTenantRequest>,
+) -> HandlerResponse {
+    if payload.confirm_tenant_id != tenant_id {
+        return HandlerResponse::new(StatusCode::BAD_REQUEST)
+            .message("Confirmation does not match the tenant being deleted")
+            .data(json!({ "error": "confirmation_mismatch" }));
+    }
+
+    let result: anyhow::Result<Option<TenantJob>> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+        if !tenant_exists(pool, tenant_id).await? {
+            return Ok(None);
+        }
+
+        let job: TenantJob = create_job(pool, tenant_id, JobKind::Delete).await?;
+
+        let job_state: AppState = state.clone();
+        let job_id: Uuid = job.id;
+        let reason: Option<String> = payload.reason;
+        spawn_job(pool.clone(), job_id, async move {
+            hard_delete_tenant(&job_state, tenant_id, job_id, reason).await
+        });
+
+        Ok(Some(job))
+    }.await;
+
+    match result {
+        Ok(Some(job)) => HandlerResponse::new(StatusCode::ACCEPTED)
+            .message("Tenant deletion started")
+            .data(job.to_json()),
+        Ok(None) => tenant_not_found(),
+        Err(e) => {
+            tracing::error!("Starting tenant deletion failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to start tenant deletion")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Imports an export archive (request body) as a new tenant
+pub async fn import_tenant(
+    State(state): State<AppState>,
+    Query(options): Query<ImportOptions>,
+    body: Bytes,
+) -> HandlerResponse {
+    // Parse up front so a malformed archive is rejected before any job exists
+    let max_uncompressed_bytes: u64 = state.environment.import_max_uncompressed_bytes;
+    let parsed: anyhow::Result<TenantSnapshot> = tokio::task::spawn_blocking(move || read_archive(&body, max_uncompressed_bytes))
+        .await
+        .unwrap_or_else(|e| Err(e.into()));
+    let snapshot: TenantSnapshot = match parsed {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            return HandlerResponse::new(StatusCode::BAD_REQUEST)
+                .message("Invalid export archive")
+                .data(json!({ "error": "invalid_archive", "details": format!("{:#}", e) }));
+        }
+    };
+
+    let new_tenant_id: Uuid = Uuid::new_v4();
+    let result: anyhow::Result<TenantJob> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+        let job: TenantJob = create_job(pool, new_tenant_id, JobKind::Import).await?;
+
+        let database = state.database.clone();
+        spawn_job(pool.clone(), job.id, async move {
+            import_snapshot(&database, snapshot, new_tenant_id, options).await
+        });
+
+        Ok(job)
+    }.await;
+
+    match result {
+        Ok(job) => HandlerResponse::new(StatusCode::ACCEPTED)
+            .message("Import started")
+            .data(job.to_json()),
+        Err(e) => {
+            tracing::error!("Starting tenant import failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to start import")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Copies an existing tenant under a new id
+pub async fn clone_tenant(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Json(options): Json<ImportOptions>,
+) -> HandlerResponse {
+    let new_tenant_id: Uuid = Uuid::new_v4();
+
+    let result: anyhow::Result<Option<TenantJob>> = async {
+        let pool: &sqlx::PgPool = state.database.get_pool()?;
+        if !tenant_exists(pool, tenant_id).await? {
+            return Ok(None);
+        }
+
+        let job: TenantJob = create_job(pool, new_tenant_id, JobKind::Clone).await?;
+
+        let database = state.database.clone();
+        spawn_job(pool.clone(), job.id, async move {
+            let snapshot: TenantSnapshot = read_live_tenant(&database, tenant_id).await?;
+            import_snapshot(&database, snapshot, new_tenant_id, options).await
+        });
+
+        Ok(Some(job))
+    }.await;
+
+    match result {
+        Ok(Some(job)) => HandlerResponse::new(StatusCode::ACCEPTED)
+            .message("Clone started")
+            .data(job.to_json()),
+        Ok(None) => tenant_not_found(),
+        Err(e) => {
+            tracing::error!("Starting tenant clone failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to start clone")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Returns the status of a job
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> HandlerResponse {
+    match load_job(&state, job_id).await {
+        Ok(Some(job)) => HandlerResponse::new(StatusCode::OK)
+            .message("Job retrieved successfully")
+            .data(job.to_json()),
+        Ok(None) => job_not_found(),
+        Err(e) => {
+            tracing::error!("Fetching job failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve job")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+/// Streams the archive of a completed export job
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> Response {
+    let job: TenantJob = match load_job(&state, job_id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return job_not_found().into_response(),
+        Err(e) => {
+            tracing::error!("Fetching job failed: {}", e);
+            return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve job")
+                .data(json!({ "error": e.to_string() }))
+                .into_response();
+        }
+    };
+
+    let artifact_path: &str = match job.artifact_path.as_deref() {
+        Some(path) if job.status == JobStatus::Completed.as_str() => path,
+        _ => {
+            return HandlerResponse::new(StatusCode::CONFLICT)
+                .message("Job has no downloadable archive")
+                .data(json!({ "error": "export_not_ready", "status": job.status }))
+                .into_response();
+        }
+    };
+
+    let file: tokio::fs::File = match tokio::fs::File::open(artifact_path).await {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::error!("Opening export archive {} failed: {}", artifact_path, e);
+            return HandlerResponse::new(StatusCode::GONE)
+                .message("Export archive is no longer available")
+                .data(json!({ "error": "export_missing" }))
+                .into_response();
+        }
+    };
+    let size_bytes: Option<u64> = file.metadata().await.ok().map(|metadata: std::fs::Metadata| metadata.len());
+
+    let disposition: String = format!("attachment; filename=\"tenant-{}-export.zip\"", job.tenant_id);
+    let mut response: Response = (
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        // Streamed from disk, archives can be large
+        Body::from_stream(ReaderStream::new(file)),
+    ).into_response();
+    if let Some(size_bytes) = size_bytes {
+        response.headers_mut().insert(header::CONTENT_LENGTH, header::HeaderValue::from(size_bytes));
+    }
+
+    // Binary body, skip the JSON response wrapper
+    response.extensions_mut().insert(RawBody);
+    response
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+async fn tenant_exists(pool: &sqlx::PgPool, tenant_id: Uuid) -> anyhow::Result<bool> {
+    Ok(sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM tenants WHERE id = $1)")
+        .bind(tenant_id)
+        .fetch_one(pool)
+        .await?)
+}
+
+async fn load_job(state: &AppState, job_id: Uuid) -> anyhow::Result<Option<TenantJob>> {
+    find_job(state.database.get_pool()?, job_id).await
+}
+
+fn tenant_not_found() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::NOT_FOUND)
+        .message("Tenant not found")
+        .data(json!({ "error": "tenant_not_found" }))
+}
+
+fn job_not_found() -> HandlerResponse {
+    HandlerResponse::new(StatusCode::NOT_FOUND)
+        .message("Job not found")
+        .data(json!({ "error": "job_not_found" }))
+}
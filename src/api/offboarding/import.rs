@@ -0,0 +1,555 @@
+// Tenant import from export archives or live tenants, with id remapping and anonymization
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{Cursor, Read};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use crate::api::plans::quota::{fetch_plan, PlanLimits, QuotaExceeded};
+use crate::config::tenant_settings::TenantSettings;
+use crate::database::tenant_tables::{find_tenant_table, PiiKind, TenantTable, TENANT_TABLES};
+use crate::database::DatabaseService;
+use super::export::{begin_snapshot, fetch_tenant_row, EXPORT_FORMAT_NAME, EXPORT_FORMAT_VERSION};
+use super::jobs::JobOutput;
+
+/// Rows of one table, as JSON objects keyed by column
+pub type TableRows = (&'static TenantTable, Vec<Map<String, Value>>);
+
+/// Tenant data ready for import, tables in registry order
+#[derive(Debug)]
+pub struct TenantSnapshot {
+    pub source_tenant_id: Option<Uuid>,
+    pub tenant: Map<String, Value>,
+    pub tables: Vec<TableRows>,
+}
+
+/// How the imported tenant is created
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportOptions {
+    /// Name of the new tenant (defaults to the source name)
+    pub name: Option<String>,
+    /// Plan of the new tenant (defaults to the source plan, matched by code)
+    pub plan_code: Option<String>,
+    /// Replace personal data (emails, names, password hashes) on the way in
+    #[serde(default)]
+    pub anonymize: bool,
+}
+
+// =============================================================================
+// SOURCES
+// =============================================================================
+
+/// Parses an archive produced by `export_tenant`. Reading stops once the decompressed
+/// entries add up to more than `max_uncompressed_bytes` (`IMPORT_MAX_UNCOMPRESSED_BYTES`).
+pub fn read_archive(bytes: &[u8], max_uncompressed_bytes: u64) -> Result<TenantSnapshot> {
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(bytes)).context("Not a zip archive")?;
+    let mut budget: ArchiveBudget = ArchiveBudget { limit: max_uncompressed_bytes, remaining: max_uncompressed_bytes };
+
+    let manifest: Value = serde_json::from_slice(&read_entry(&mut archive, "manifest.json", &mut budget)?)
+        .context("Invalid manifest.json")?;
+    if manifest["format"] != EXPORT_FORMAT_NAME {
+        bail!("Archive is not a tenant export");
+    }
+    let version: u64 = manifest["version"].as_u64().context("Manifest has no version")?;
+    if version == 0 || version > u64::from(EXPORT_FORMAT_VERSION) {
+        bail!("Unsupported export format version {} (supported: up to {})", version, EXPORT_FORMAT_VERSION);
+    }
+
+    let tenant: Value = serde_json::from_slice(&read_entry(&mut archive, "tenant.json", &mut budget)?)
+        .context("Invalid tenant.json")?;
+
+    let mut rows_by_table: HashMap<&'static str, Vec<Map<String, Value>>> = HashMap::new();
+    for entry in manifest["tables"].as_array().context("Manifest has no table list")? {
+        let name: &str = entry["name"].as_str().context("Manifest table without name")?;
+        let file: &str = entry["file"].as_str().context("Manifest table without file")?;
+        let table: &'static TenantTable = find_tenant_table(name)
+            .with_context(|| format!("Archive contains unknown table {}", name))?;
+
+        let content: Vec<u8> = read_entry(&mut archive, file, &mut budget)?;
+        let rows: Vec<Map<String, Value>> = content
+            .split(|b: &u8| *b == b'\n')
+            .filter(|line: &&[u8]| !line.is_empty())
+            .enumerate()
+            .map(|(i, line): (usize, &[u8])| {
+                serde_json::from_slice::<Value>(line)
+                    .map_err(|e| anyhow!("{} line {}: {}", file, i + 1, e))
+                    .and_then(|row: Value| into_object(row, file))
+            })
+            .collect::<Result<Vec<Map<String, Value>>>>()?;
+        rows_by_table.insert(table.name, rows);
+    }
+
+    // Tables added after the export was taken simply import empty
+    let tables: Vec<TableRows> = TENANT_TABLES
+        .iter()
+        .map(|table: &'static TenantTable| (table, rows_by_table.remove(table.name).unwrap_or_default()))
+        .collect();
+
+    Ok(TenantSnapshot {
+        source_tenant_id: manifest["tenant_id"].as_str().and_then(|id: &str| Uuid::parse_str(id).ok()),
+        tenant: into_object(tenant, "tenant.json")?,
+        tables,
+    })
+}
+
+/// Reads a tenant straight from the database, from one consistent snapshot
+pub async fn read_live_tenant(database: &DatabaseService, tenant_id: Uuid) -> Result<TenantSnapshot> {
+    let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = begin_snapshot(database, tenant_id).await?;
+    let tenant: Value = fetch_tenant_row(&mut tx, tenant_id).await?;
+
+    let mut tables: Vec<TableRows> = Vec::with_capacity(TENANT_TABLES.len());
+    for table in TENANT_TABLES {
+        // Table names come from the static registry, never from input
+        let query: String = format!(
+            "SELECT to_jsonb(t) FROM {} t WHERE tenant_id = $1 ORDER BY {}",
+            table.name, table.order_by
+        );
+        let rows: Vec<Value> = sqlx::query_scalar(&query).bind(tenant_id).fetch_all(&mut *tx).await?;
+
+        let rows: Vec<Map<String, Value>> = rows
+            .into_iter()
+            .map(|row: Value| into_object(row, table.name))
+            .collect::<Result<Vec<Map<String, Value>>>>()?;
+        tables.push((table, rows));
+    }
+
+    tx.commit().await.context("Failed to commit transaction")?;
+
+    Ok(TenantSnapshot {
+        source_tenant_id: Some(tenant_id),
+        tenant: into_object(tenant, "tenants")?,
+        tables,
+    })
+}
+
+// =============================================================================
+// IMPORT
+// =============================================================================
+
+/// Creates `new_tenant_id` from a snapshot.
+///
+/// Every registered id column gets a fresh UUID and references are rewritten
+/// through the same mapping, so foreign keys between imported rows still hold.
+/// Rows are inserted inside `with_tenant` for the new tenant, letting RLS and
+/// the table constraints re-validate everything; any failure rolls back the
+/// whole import, including the tenant row.
+pub async fn import_snapshot(
+    database: &DatabaseService,
+    snapshot: TenantSnapshot,
+    new_tenant_id: Uuid,
+    options: ImportOptions,
+) -> Result<JobOutput> {
+    // 1. Remap, scrub and validate before touching the database
+    let tables: Vec<TableRows> = prepare_rows(snapshot.tables, new_tenant_id, options.anonymize)?;
+    validate_settings(&tables)?;
+
+    let source_name: Option<String> = snapshot.tenant.get("name").and_then(|v: &Value| v.as_str()).map(str::to_owned);
+    let name: String = match (options.name, options.anonymize) {
+        (Some(name), _) => name,
+        (None, false) => source_name.context("Tenant row has no name")?,
+        (None, true) => format!("Tenant {}", &new_tenant_id.simple().to_string()[..8]),
+    };
+    let plan_code: Option<String> = options.plan_code.or_else(|| {
+        snapshot.tenant.get("plan_code").and_then(|v: &Value| v.as_str()).map(str::to_owned)
+    });
+
+    // 2. Insert tenant and rows in one tenant-scoped transaction
+    let report: Value = database.with_tenant(new_tenant_id, |tx| Box::pin(async move {
+        let plan_id: Option<Uuid> = match &plan_code {
+            Some(code) => Some(
+                sqlx::query_scalar("SELECT id FROM plans WHERE code = $1")
+                    .bind(code)
+                    .fetch_optional(&mut **tx)
+                    .await?
+                    .with_context(|| format!("Unknown plan {}", code))?
+            ),
+            None => None,
+        };
+
+        sqlx::query("INSERT INTO tenants (id, name, plan_id) VALUES ($1, $2, COALESCE($3, default_plan_id()))")
+            .bind(new_tenant_id)
+            .bind(&name)
+            .bind(plan_id)
+            .execute(&mut **tx)
+            .await
+            .context("Failed to create tenant")?;
+
+        // The target plan's quotas apply to the imported data as they would to new rows
+        enforce_import_quotas(tx, new_tenant_id, &tables).await?;
+
+        // Flag definitions are per environment; overrides of unknown flags are dropped
+        let known_flags: HashSet<String> = sqlx::query_scalar::<_, String>("SELECT key FROM feature_flags")
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut inserted: Map<String, Value> = Map::new();
+        let mut skipped: Map<String, Value> = Map::new();
+        let mut ignored_columns: Map<String, Value> = Map::new();
+
+        for (table, mut rows) in tables {
+            let before: usize = rows.len();
+            if table.name == "tenant_feature_flags" {
+                rows.retain(|row: &Map<String, Value>| {
+                    row.get("flag_key").and_then(|v: &Value| v.as_str()).is_some_and(|k: &str| known_flags.contains(k))
+                });
+            }
+            if before != rows.len() {
+                skipped.insert(table.name.to_string(), json!(before - rows.len()));
+            }
+
+            if rows.is_empty() {
+                inserted.insert(table.name.to_string(), json!(0));
+                continue;
+            }
+
+            // Only columns that exist here; archive keys are untrusted input
+            let existing: HashSet<String> = sqlx::query_scalar::<_, String>(
+                "SELECT column_name::text FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1"
+            )
+            .bind(table.name)
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .collect();
+
+            let keys: BTreeSet<&String> = rows.iter().flat_map(|row: &Map<String, Value>| row.keys()).collect();
+            let (columns, unknown): (Vec<&String>, Vec<&String>) = keys.into_iter().partition(|k: &&String| existing.contains(*k));
+            if !unknown.is_empty() {
+                ignored_columns.insert(table.name.to_string(), json!(unknown));
+            }
+
+            let column_list: String = columns.iter().map(|c: &&String| format!("\"{}\"", c)).collect::<Vec<String>>().join(", ");
+            let query: String = format!(
+                "INSERT INTO {table} ({columns}) SELECT {columns} FROM jsonb_populate_recordset(NULL::{table}, $1)",
+                table = table.name,
+                columns = column_list,
+            );
+
+            let count: u64 = sqlx::query(&query)
+                .bind(Value::Array(rows.into_iter().map(Value::Object).collect()))
+                .execute(&mut **tx)
+                .await
+                .with_context(|| format!("Failed to import table {}", table.name))?
+                .rows_affected();
+            inserted.insert(table.name.to_string(), json!(count));
+        }
+
+        Ok(json!({
+            "inserted_rows": inserted,
+            "skipped_rows": skipped,
+            "ignored_columns": ignored_columns,
+        }))
+    })).await?;
+
+    tracing::info!("Imported tenant {} (source: {:?})", new_tenant_id, snapshot.source_tenant_id);
+
+    let mut result: Value = report;
+    result["tenant_id"] = json!(new_tenant_id);
+    result["source_tenant_id"] = json!(snapshot.source_tenant_id);
+    result["anonymized"] = json!(options.anonymize);
+
+    Ok(JobOutput { artifact_path: None, result })
+}
+
+/// Fails with `QuotaExceeded` when the snapshot holds more than the tenant's plan allows
+async fn enforce_import_quotas(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, tenant_id: Uuid, tables: &[TableRows]) -> Result<()> {
+    let plan: PlanLimits = fetch_plan(tx, tenant_id).await?;
+    let users: i64 = tables
+        .iter()
+        .find(|(table, _)| table.name == "users")
+        .map_or(0, |(_, rows)| rows.len() as i64);
+
+    if let Some(limit) = plan.max_users.map(i64::from) {
+        if users > limit {
+            return Err(QuotaExceeded { quota: "max_users", limit, current: users }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Assigns new ids, points rows at the new tenant and applies anonymization
+fn prepare_rows(
+    mut tables: Vec<TableRows>,
+    new_tenant_id: Uuid,
+    anonymize: bool,
+) -> Result<Vec<TableRows>> {
+    // 1. Allocate every new id first, so references resolve regardless of table order
+    let mut id_map: HashMap<String, Uuid> = HashMap::new();
+    for (table, rows) in &tables {
+        if let Some(column) = table.id_column {
+            for row in rows {
+                let old: &str = row.get(column).and_then(|v: &Value| v.as_str())
+                    .with_context(|| format!("{} row without {}", table.name, column))?;
+                id_map.insert(old.to_string(), Uuid::new_v4());
+            }
+        }
+    }
+
+    // 2. Rewrite rows
+    for (table, rows) in &mut tables {
+        for (index, row) in rows.iter_mut().enumerate() {
+            row.insert("tenant_id".to_string(), json!(new_tenant_id));
+
+            let remapped_columns = table.id_column.iter().chain(table.references.iter());
+            for column in remapped_columns {
+                let new_id: Option<Uuid> = match row.get(*column) {
+                    Some(Value::String(old)) => Some(*id_map.get(old)
+                        .with_context(|| format!("{}.{} references unknown id {}", table.name, column, old))?),
+                    Some(Value::Null) | None => None,
+                    Some(other) => bail!("{}.{} is not a UUID: {}", table.name, column, other),
+                };
+                if let Some(new_id) = new_id {
+                    row.insert(column.to_string(), json!(new_id));
+                }
+            }
+
+            if anonymize {
+                anonymize_row(table, row, index + 1);
+            }
+            reset_environment_state(table, row);
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Replaces personal data with placeholders that stay unique within the tenant
+fn anonymize_row(table: &TenantTable, row: &mut Map<String, Value>, sequence: usize) {
+    for (column, kind) in table.pii_columns {
+        if row.get(*column).is_none_or(Value::is_null) {
+            continue;
+        }
+
+        let replacement: String = match kind {
+            PiiKind::Email => format!("user-{}@example.invalid", sequence),
+            PiiKind::Name => format!("User {}", sequence),
+            // Not a valid bcrypt hash, so the account cannot sign in
+            PiiKind::Secret => "!".to_string(),
+        };
+        row.insert(column.to_string(), json!(replacement));
+    }
+}
+
+/// Clears state that is only valid in the source environment.
+/// Domain verification proves DNS control for the source deployment only,
+/// and a verified hostname there would collide with the original here.
+fn reset_environment_state(table: &TenantTable, row: &mut Map<String, Value>) {
+    if table.name == "tenant_domains" {
+        row.insert("status".to_string(), json!("pending"));
+        row.insert("verification_token".to_string(), json!(Uuid::new_v4().simple().to_string()));
+        row.insert("verified_at".to_string(), Value::Null);
+        row.insert("last_checked_at".to_string(), Value::Null);
+    }
+}
+
+/// Settings documents must match the current TenantSettings schema
+fn validate_settings(tables: &[TableRows]) -> Result<()> {
+    let rows = tables
+        .iter()
+        .filter(|(table, _)| table.name == "tenant_settings")
+        .flat_map(|(_, rows)| rows.iter());
+
+    for row in rows {
+        let document: Value = row.get("settings").cloned().unwrap_or(Value::Null);
+        let settings: TenantSettings = serde_json::from_value(document).context("Invalid tenant settings document")?;
+        settings.validate().map_err(|errors: Vec<String>| anyhow!("Invalid tenant settings: {}", errors.join(", ")))?;
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// HELPERS
+// =============================================================================
+
+/// Decompressed bytes an archive may still produce
+struct ArchiveBudget {
+    limit: u64,
+    remaining: u64,
+}
+
+/// Reads one archive entry, charging its decompressed size to `budget`.
+/// Never decompresses more than the remaining budget plus one byte.
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str, budget: &mut ArchiveBudget) -> Result<Vec<u8>> {
+    let entry = archive.by_name(name).with_context(|| format!("Archive has no {}", name))?;
+
+    let mut content: Vec<u8> = Vec::new();
+    entry.take(budget.remaining.saturating_add(1)).read_to_end(&mut content)?;
+    if content.len() as u64 > budget.remaining {
+        bail!("Archive exceeds {} bytes uncompressed (at {})", budget.limit, name);
+    }
+    budget.remaining -= content.len() as u64;
+
+    Ok(content)
+}
+
+fn into_object(value: Value, source: &str) -> Result<Map<String, Value>> {
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => bail!("{} contains a non-object row", source),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+    use super::*;
+
+    /// A parent/child pair, since no registered table references another yet
+    static PROJECTS: TenantTable = TenantTable { name: "projects", order_by: "id", id_column: Some("id"), references: &[], pii_columns: &[] };
+    static TASKS: TenantTable = TenantTable { name: "tasks", order_by: "id", id_column: Some("id"), references: &["project_id"], pii_columns: &[] };
+
+    fn table(name: &str) -> &'static TenantTable {
+        find_tenant_table(name).unwrap()
+    }
+
+    fn row(value: Value) -> Map<String, Value> {
+        into_object(value, "test").unwrap()
+    }
+
+    fn zip(entries: &[(&str, String)]) -> Vec<u8> {
+        let mut zip: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn manifest(version: u32) -> String {
+        json!({
+            "format": EXPORT_FORMAT_NAME,
+            "version": version,
+            "tenant_id": Uuid::nil(),
+            "tables": [{ "name": "users", "file": "tables/users.jsonl", "rows": 2 }],
+        }).to_string()
+    }
+
+    fn archive_entries(version: u32) -> [(&'static str, String); 3] {
+        [
+            ("manifest.json", manifest(version)),
+            ("tenant.json", json!({ "name": "Acme", "plan_code": "free" }).to_string()),
+            ("tables/users.jsonl", "{\"id\":\"a\"}\n\n{\"id\":\"b\"}\n".to_string()),
+        ]
+    }
+
+    fn archive(version: u32) -> Vec<u8> {
+        zip(&archive_entries(version))
+    }
+
+    // =========================================================================
+    // ID REMAPPING
+    // =========================================================================
+
+    #[test]
+    fn ids_are_replaced_and_references_follow_them() {
+        let new_tenant_id: Uuid = Uuid::new_v4();
+        let project: Uuid = Uuid::new_v4();
+        let tables: Vec<TableRows> = vec![
+            // Children first: ids are allocated before any row is rewritten
+            (&TASKS, vec![
+                row(json!({ "id": Uuid::new_v4(), "project_id": project, "tenant_id": Uuid::nil() })),
+                row(json!({ "id": Uuid::new_v4(), "project_id": null })),
+            ]),
+            (&PROJECTS, vec![row(json!({ "id": project, "tenant_id": Uuid::nil() }))]),
+        ];
+
+        let prepared: Vec<TableRows> = prepare_rows(tables, new_tenant_id, false).unwrap();
+        let (tasks, projects) = (&prepared[0].1, &prepared[1].1);
+        let new_project: &Value = &projects[0]["id"];
+        assert_ne!(new_project, &json!(project));
+        assert_eq!(&tasks[0]["project_id"], new_project);
+        assert_eq!(tasks[1]["project_id"], Value::Null);
+        assert!(prepared.iter().flat_map(|(_, rows)| rows).all(|row: &Map<String, Value>| row["tenant_id"] == json!(new_tenant_id)));
+    }
+
+    #[test]
+    fn dangling_or_malformed_ids_are_rejected() {
+        let dangling: Vec<TableRows> = vec![(&TASKS, vec![row(json!({ "id": "a", "project_id": "missing" }))])];
+        let error: String = prepare_rows(dangling, Uuid::new_v4(), false).unwrap_err().to_string();
+        assert_eq!(error, "tasks.project_id references unknown id missing");
+
+        let malformed: Vec<TableRows> = vec![(&TASKS, vec![row(json!({ "id": "a", "project_id": 7 }))])];
+        assert!(prepare_rows(malformed, Uuid::new_v4(), false).is_err());
+
+        let without_id: Vec<TableRows> = vec![(&PROJECTS, vec![row(json!({ "name": "x" }))])];
+        assert!(prepare_rows(without_id, Uuid::new_v4(), false).is_err());
+    }
+
+    #[test]
+    fn anonymization_replaces_personal_data_only() {
+        let users: Vec<Map<String, Value>> = vec![
+            row(json!({ "id": "a", "email": "ada@example.com", "full_name": "Ada", "password_hash": "$2b$..." })),
+            row(json!({ "id": "b", "email": "grace@example.com", "full_name": null, "password_hash": "$2b$..." })),
+        ];
+        let prepared: Vec<TableRows> = prepare_rows(vec![(table("users"), users)], Uuid::new_v4(), true).unwrap();
+        let rows: &Vec<Map<String, Value>> = &prepared[0].1;
+        assert_eq!((&rows[0]["email"], &rows[0]["full_name"], &rows[0]["password_hash"]), (&json!("user-1@example.invalid"), &json!("User 1"), &json!("!")));
+        assert_eq!((&rows[1]["email"], &rows[1]["full_name"]), (&json!("user-2@example.invalid"), &Value::Null));
+    }
+
+    #[test]
+    fn imported_domains_must_be_verified_again() {
+        let domain: Map<String, Value> = row(json!({ "id": "a", "status": "verified", "verification_token": "old", "verified_at": "2026-01-01T00:00:00Z" }));
+        let prepared: Vec<TableRows> = prepare_rows(vec![(table("tenant_domains"), vec![domain])], Uuid::new_v4(), false).unwrap();
+        let domain: &Map<String, Value> = &prepared[0].1[0];
+        assert_eq!((&domain["status"], &domain["verified_at"]), (&json!("pending"), &Value::Null));
+        assert_ne!(domain["verification_token"], "old");
+    }
+
+    #[test]
+    fn invalid_settings_documents_are_rejected() {
+        let settings = |document: Value| vec![(table("tenant_settings"), vec![row(json!({ "settings": document }))])];
+        assert!(validate_settings(&settings(json!({ "locale": "es-AR" }))).is_ok());
+        assert!(validate_settings(&settings(json!({ "locale": "Spanish" }))).is_err());
+        assert!(validate_settings(&settings(json!({ "session_ttl_seconds": "long" }))).is_err());
+    }
+
+    // =========================================================================
+    // ARCHIVES
+    // =========================================================================
+
+    #[test]
+    fn archives_are_read_in_registry_order() {
+        let snapshot: TenantSnapshot = read_archive(&archive(EXPORT_FORMAT_VERSION), u64::MAX).unwrap();
+        assert_eq!(snapshot.source_tenant_id, Some(Uuid::nil()));
+        assert_eq!(snapshot.tenant["name"], "Acme");
+
+        let names: Vec<&str> = snapshot.tables.iter().map(|(table, _)| table.name).collect();
+        assert_eq!(names, TENANT_TABLES.iter().map(|t: &TenantTable| t.name).collect::<Vec<&str>>());
+        let users: &Vec<Map<String, Value>> = &snapshot.tables[0].1;
+        assert_eq!(users.iter().map(|row: &Map<String, Value>| row["id"].clone()).collect::<Vec<Value>>(), [json!("a"), json!("b")]);
+        // Tables missing from the archive import empty
+        assert!(snapshot.tables[1..].iter().all(|(_, rows)| rows.is_empty()));
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        for version in [0, EXPORT_FORMAT_VERSION + 1] {
+            let error: String = read_archive(&archive(version), u64::MAX).unwrap_err().to_string();
+            assert!(error.starts_with("Unsupported export format version"), "{}", error);
+        }
+        let foreign: Vec<u8> = zip(&[("manifest.json", json!({ "format": "other", "version": 1 }).to_string())]);
+        assert_eq!(read_archive(&foreign, u64::MAX).unwrap_err().to_string(), "Archive is not a tenant export");
+        assert!(read_archive(b"not a zip", u64::MAX).is_err());
+    }
+
+    #[test]
+    fn the_uncompressed_budget_covers_every_entry() {
+        let bytes: Vec<u8> = archive(EXPORT_FORMAT_VERSION);
+        let total: u64 = archive_entries(EXPORT_FORMAT_VERSION).iter().map(|(_, content)| content.len() as u64).sum();
+
+        assert!(read_archive(&bytes, total).is_ok());
+        let error: String = read_archive(&bytes, total - 1).unwrap_err().to_string();
+        assert_eq!(error, format!("Archive exceeds {} bytes uncompressed (at tables/users.jsonl)", total - 1));
+    }
+}
@@ -0,0 +1,260 @@
+// Background tenant jobs (export, deletion, import, clone) tracked in `tenant_jobs`
+
+use std::future::Future;
+use std::time::Duration;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::export::archive_path;
+
+/// Kind of work a job performs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Export,
+    Delete,
+    Import,
+    Clone,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Export => "export",
+            JobKind::Delete => "delete",
+            JobKind::Import => "import",
+            JobKind::Clone => "clone",
+        }
+    }
+}
+
+/// Lifecycle of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Job row as stored in `tenant_jobs`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TenantJob {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub kind: String,
+    pub status: String,
+    /// Server-side file produced by the job (export archive)
+    pub artifact_path: Option<String>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl TenantJob {
+    /// Serializes the job; the artifact path stays server-side, a download link is given instead
+    pub fn to_json(&self) -> Value {
+        let download_url: Option<String> = self.artifact_path
+            .as_ref()
+            .map(|_| format!("/admin/jobs/{}/download", self.id));
+
+        json!({
+            "id": self.id,
+            "tenant_id": self.tenant_id,
+            "kind": self.kind,
+            "status": self.status,
+            "result": self.result,
+            "error": self.error,
+            "download_url": download_url,
+            "created_at": self.created_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+            "started_at": self.started_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+            "finished_at": self.finished_at.map(|d: DateTime<Utc>| d.to_rfc3339()),
+        })
+    }
+}
+
+/// How often a running job refreshes its heartbeat
+const JOB_HEARTBEAT_SECS: u64 = 30;
+
+/// Unfinished jobs without a heartbeat for this long are considered interrupted
+const JOB_STALE_AFTER_SECS: u64 = 3 * JOB_HEARTBEAT_SECS;
+
+const JOB_COLUMNS: &str = "id, tenant_id, kind, status, artifact_path, result, error, created_at, started_at, finished_at";
+
+/// Inserts a pending job
+pub async fn create_job(pool: &PgPool, tenant_id: Uuid, kind: JobKind) -> Result<TenantJob> {
+    let query: String = format!(
+        "INSERT INTO tenant_jobs (tenant_id, kind) VALUES ($1, $2) RETURNING {}",
+        JOB_COLUMNS
+    );
+
+    Ok(sqlx::query_as::<_, TenantJob>(&query)
+        .bind(tenant_id)
+        .bind(kind.as_str())
+        .fetch_one(pool)
+        .await?)
+}
+
+/// Fetches a job by id
+pub async fn find_job(pool: &PgPool, job_id: Uuid) -> Result<Option<TenantJob>> {
+    let query: String = format!("SELECT {} FROM tenant_jobs WHERE id = $1", JOB_COLUMNS);
+
+    Ok(sqlx::query_as::<_, TenantJob>(&query)
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Output of a successful job
+pub struct JobOutput {
+    pub artifact_path: Option<String>,
+    pub result: Value,
+}
+
+/// Runs `work` in the background, recording its progress and outcome on the job row.
+/// The row's heartbeat is refreshed while the work runs, see `fail_interrupted_jobs`.
+pub fn spawn_job<F>(pool: PgPool, job_id: Uuid, work: F)
+where
+    F: Future<Output = Result<JobOutput>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(e) = mark_running(&pool, job_id).await {
+            tracing::error!("Failed to start job {}: {}", job_id, e);
+            return;
+        }
+
+        let mut heartbeat: tokio::time::Interval = tokio::time::interval(Duration::from_secs(JOB_HEARTBEAT_SECS));
+        heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // mark_running already stamped the first one
+        heartbeat.tick().await;
+
+        tokio::pin!(work);
+        let result: Result<JobOutput> = loop {
+            tokio::select! {
+                result = &mut work => break result,
+                _ = heartbeat.tick() => {
+                    if let Err(e) = beat(&pool, job_id).await {
+                        tracing::warn!("Failed to refresh heartbeat of job {}: {}", job_id, e);
+                    }
+                }
+            }
+        };
+
+        let outcome: Result<()> = match result {
+            Ok(output) => sqlx::query(
+                "UPDATE tenant_jobs SET status = $2, artifact_path = $3, result = $4, finished_at = NOW() WHERE id = $1"
+            )
+            .bind(job_id)
+            .bind(JobStatus::Completed.as_str())
+            .bind(output.artifact_path)
+            .bind(output.result)
+            .execute(&pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.into()),
+            Err(e) => {
+                tracing::error!("Job {} failed: {:#}", job_id, e);
+                sqlx::query("UPDATE tenant_jobs SET status = $2, error = $3, finished_at = NOW() WHERE id = $1")
+                    .bind(job_id)
+                    .bind(JobStatus::Failed.as_str())
+                    .bind(format!("{:#}", e))
+                    .execute(&pool)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.into())
+            }
+        };
+
+        if let Err(e) = outcome {
+            tracing::error!("Failed to record outcome of job {}: {}", job_id, e);
+        }
+    });
+}
+
+async fn mark_running(pool: &PgPool, job_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE tenant_jobs SET status = $2, started_at = NOW(), heartbeat_at = NOW() WHERE id = $1")
+        .bind(job_id)
+        .bind(JobStatus::Running.as_str())
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn beat(pool: &PgPool, job_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE tenant_jobs SET heartbeat_at = NOW() WHERE id = $1")
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Marks jobs as failed whose process stopped before they finished (pending or running,
+/// with no heartbeat for `JOB_STALE_AFTER_SECS`), removing the partial archives of exports.
+/// Jobs of other live instances keep their heartbeat fresh and are left alone.
+pub async fn fail_interrupted_jobs(pool: &PgPool, export_dir: &str) -> Result<Vec<Uuid>> {
+    let interrupted: Vec<(Uuid, Uuid, String)> = sqlx::query_as(
+        r#"
+        UPDATE tenant_jobs
+        SET status = $1, error = $2, finished_at = NOW()
+        WHERE status IN ($3, $4)
+          AND COALESCE(heartbeat_at, created_at) < NOW() - make_interval(secs => $5)
+        RETURNING id, tenant_id, kind
+        "#
+    )
+    .bind(JobStatus::Failed.as_str())
+    .bind("Interrupted: the process running the job stopped")
+    .bind(JobStatus::Pending.as_str())
+    .bind(JobStatus::Running.as_str())
+    .bind(JOB_STALE_AFTER_SECS as f64)
+    .fetch_all(pool)
+    .await?;
+
+    for (job_id, tenant_id, kind) in &interrupted {
+        tracing::warn!("Job {} ({}) was interrupted and is marked failed", job_id, kind);
+        if kind == JobKind::Export.as_str() {
+            let partial_path: std::path::PathBuf = archive_path(export_dir, *tenant_id, *job_id).with_extension("zip.partial");
+            if let Err(e) = tokio::fs::remove_file(&partial_path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Failed to remove {}: {}", partial_path.display(), e);
+                }
+            }
+        }
+    }
+
+    Ok(interrupted.into_iter().map(|(job_id, _, _)| job_id).collect())
+}
+
+/// Runs `fail_interrupted_jobs` now and then every heartbeat period, for jobs whose
+/// process died while this one keeps running
+pub fn start_job_sweeper(pool: PgPool, export_dir: String) {
+    tokio::spawn(async move {
+        let mut ticker: tokio::time::Interval = tokio::time::interval(Duration::from_secs(JOB_HEARTBEAT_SECS));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if pool.is_closed() {
+                return;
+            }
+            if let Err(e) = fail_interrupted_jobs(&pool, &export_dir).await {
+                tracing::warn!("Failed to check for interrupted jobs: {}", e);
+            }
+        }
+    });
+}
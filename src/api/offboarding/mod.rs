@@ -0,0 +1,8 @@
+// Tenant data lifecycle module: export, import/clone, hard deletion and their background jobs
+
+pub mod deletion;
+pub mod export;
+pub mod handler;
+pub mod import;
+pub mod jobs;
+pub mod routes;
@@ -0,0 +1,22 @@
+// Tenant data lifecycle route definitions
+
+use axum::{extract::DefaultBodyLimit, routing::{delete, get, post}, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Upload limit for import archives, above the general request body limit
+const MAX_IMPORT_ARCHIVE_BYTES: usize = 100 * 1024 * 1024;
+
+/// Creates router with admin endpoints for tenant export, import, cloning, deletion and job status
+pub fn admin_offboarding_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/tenants/{tenant_id}", delete(handler::delete_tenant))
+        .route("/admin/tenants/{tenant_id}/exports", post(handler::start_export))
+        .route("/admin/tenants/{tenant_id}/clone", post(handler::clone_tenant))
+        .route(
+            "/admin/tenants/import",
+            post(handler::import_tenant).layer(DefaultBodyLimit::max(MAX_IMPORT_ARCHIVE_BYTES)),
+        )
+        .route("/admin/jobs/{job_id}", get(handler::get_job))
+        .route("/admin/jobs/{job_id}/download", get(handler::download_export))
+}
@@ -0,0 +1,87 @@
+// Tenant plan and usage handlers
+
+use axum::{extract::{Extension, State}, http::StatusCode};
+use chrono::Utc;
+use serde_json::json;
+
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::utils::response_handler::HandlerResponse;
+use super::quota::{fetch_plan, PlanLimits};
+
+/// Returns the current billing period key ("YYYY-MM", UTC)
+pub fn current_period() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+/// Returns the plan of the current tenant and its usage against each quota
+pub async fn get_usage(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+) -> HandlerResponse {
+    let tenant_id: uuid::Uuid = ctx.tenant_id;
+
+    // 1. Plan and user count (Scoped Execution)
+    let result: anyhow::Result<(PlanLimits, i64)> = state.database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        let plan: PlanLimits = fetch_plan(tx, tenant_id).await?;
+        let users: i64 = sqlx::query_scalar("SELECT count(*) FROM users")
+            .fetch_one(&mut **tx)
+            .await?;
+
+        Ok((plan, users))
+    })).await;
+
+    let (plan, users): (PlanLimits, i64) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("Loading usage for tenant {} failed: {}", tenant_id, e);
+            return HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Failed to retrieve usage")
+                .data(json!({ "error": e.to_string() }));
+        }
+    };
+
+    // 2. Request counter (cache); reported as unavailable rather than failing the request
+    let period: String = current_period();
+    let requests: Option<i64> = match state.cache.get_monthly_requests(&tenant_id, &period).await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            tracing::warn!("Failed to read request counter for tenant {}: {}", tenant_id, e);
+            None
+        }
+    };
+
+    HandlerResponse::new(StatusCode::OK)
+        .message("Usage retrieved successfully")
+        .data(json!({
+            "plan": { "code": plan.code, "name": plan.name },
+            "period": period,
+            "usage": {
+                "users": { "used": users, "limit": plan.max_users },
+                "requests_per_month": { "used": requests, "limit": plan.max_requests_per_month },
+            },
+            "limits": {
+                "rate_limit_per_minute": plan.rate_limit_per_minute,
+            }
+        }))
+}
+
+/// Loads a tenant's plan, checking the cache before the database
+pub async fn load_tenant_plan(state: &AppState, tenant_id: uuid::Uuid) -> anyhow::Result<PlanLimits> {
+    match state.cache.get_tenant_plan(&tenant_id).await {
+        Ok(Some(cached)) => return Ok(cached),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to read plan for tenant {} from cache: {}", tenant_id, e),
+    }
+
+    // tenants and plans are global tables, no tenant transaction needed
+    let mut conn: sqlx::pool::PoolConnection<sqlx::Postgres> = state.database.get_pool()?.acquire().await?;
+    let plan: PlanLimits = fetch_plan(&mut conn, tenant_id).await?;
+
+    if let Err(e) = state.cache.set_tenant_plan(&tenant_id, &plan).await {
+        // Don't fail request if cache fails, just log it
+        tracing::warn!("Failed to cache plan for tenant {}: {}", tenant_id, e);
+    }
+
+    Ok(plan)
+}
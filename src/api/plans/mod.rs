@@ -0,0 +1,5 @@
+// Tenant plans, quotas and usage module
+
+pub mod handler;
+pub mod quota;
+pub mod routes;
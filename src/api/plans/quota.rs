@@ -0,0 +1,121 @@
+// Plan quotas and their enforcement
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::utils::response_handler::HandlerResponse;
+
+/// Plan assigned to a tenant and its quotas (None = unlimited).
+/// `plans.max_api_keys` is not loaded: there are no API keys to enforce it on yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PlanLimits {
+    pub code: String,
+    pub name: String,
+    pub max_users: Option<i32>,
+    pub max_requests_per_month: Option<i64>,
+    pub rate_limit_per_minute: Option<i32>,
+}
+
+/// Raised when an operation would exceed a plan quota.
+/// Travels through `anyhow` so it can be downcast back out of `with_tenant`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// Quota name as exposed to clients (e.g. "max_users")
+    pub quota: &'static str,
+    pub limit: i64,
+    pub current: i64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "quota {} exceeded ({} of {})", self.quota, self.current, self.limit)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl From<QuotaExceeded> for HandlerResponse {
+    fn from(e: QuotaExceeded) -> Self {
+        HandlerResponse::new(StatusCode::PAYMENT_REQUIRED)
+            .message("Plan limit reached, upgrade your plan to continue")
+            .data(json!({
+                "error": "quota_exceeded",
+                "quota": e.quota,
+                "limit": e.limit,
+                "current": e.current,
+            }))
+    }
+}
+
+/// Reads the plan of a tenant
+pub async fn fetch_plan(conn: &mut PgConnection, tenant_id: Uuid) -> anyhow::Result<PlanLimits> {
+    let plan: PlanLimits = sqlx::query_as(
+        r#"
+        SELECT p.code, p.name, p.max_users, p.max_requests_per_month, p.rate_limit_per_minute
+        FROM tenants t
+        JOIN plans p ON p.id = t.plan_id
+        WHERE t.id = $1
+        "#
+    )
+    .bind(tenant_id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(plan)
+}
+
+/// Fails with `QuotaExceeded` when the tenant has more users than its plan allows.
+/// Must run right after inserting the user, in the same tenant transaction: the
+/// tenant row is locked before counting, so concurrent registrations are counted one
+/// after the other, and the failed transaction rolls the insert back.
+pub async fn enforce_user_quota(conn: &mut PgConnection, tenant_id: Uuid) -> anyhow::Result<()> {
+    let max_users: Option<i32> = sqlx::query_scalar(
+        r#"
+        SELECT p.max_users
+        FROM tenants t
+        JOIN plans p ON p.id = t.plan_id
+        WHERE t.id = $1
+        FOR UPDATE OF t
+        "#
+    )
+    .bind(tenant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let limit: i64 = match max_users {
+        Some(limit) => i64::from(limit),
+        None => return Ok(()),
+    };
+
+    // RLS scopes the count to the current tenant; it includes the user just inserted
+    let users: i64 = sqlx::query_scalar("SELECT count(*) FROM users")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if users > limit {
+        return Err(QuotaExceeded { quota: "max_users", limit, current: users - 1 }.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_errors_survive_anyhow_and_become_402() {
+        let error: anyhow::Error = QuotaExceeded { quota: "max_users", limit: 5, current: 5 }.into();
+        assert_eq!(error.to_string(), "quota max_users exceeded (5 of 5)");
+
+        let quota: QuotaExceeded = error.downcast_ref::<QuotaExceeded>().cloned().expect("not a QuotaExceeded");
+        let response: HandlerResponse = quota.into();
+        assert_eq!(response.status_code, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(response.data, json!({ "error": "quota_exceeded", "quota": "max_users", "limit": 5, "current": 5 }));
+    }
+}
+
+// End of file: /src/api/plans/quota.rs
@@ -0,0 +1,11 @@
+// Tenant plan and usage route definitions
+
+use axum::{routing::get, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Creates router with usage endpoints for the current tenant
+pub fn plan_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenant/usage", get(handler::get_usage))
+}
@@ -0,0 +1,196 @@
+// Tenant settings handlers
+
+use axum::{extract::{Extension, State}, http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::api::middleware::tenant::TenantContext;
+use crate::config::state::AppState;
+use crate::config::tenant_settings::{TenantSettings, TenantSettingsPatch, VersionedSettings};
+use crate::database::{DatabaseService, IsolationLevel, TxOptions};
+use crate::utils::response_handler::HandlerResponse;
+
+// =============================================================================
+// DTOs
+// =============================================================================
+
+/// PATCH body: any subset of the settings plus an optional optimistic-lock version
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateSettingsRequest {
+    /// When present, the update only applies if the stored version still matches
+    pub expected_version: Option<i32>,
+    pub allow_signup: Option<bool>,
+    pub require_mfa: Option<bool>,
+    pub session_ttl_seconds: Option<u64>,
+    pub locale: Option<String>,
+}
+
+impl UpdateSettingsRequest {
+    fn into_patch(self) -> (Option<i32>, TenantSettingsPatch) {
+        (self.expected_version, TenantSettingsPatch {
+            allow_signup: self.allow_signup,
+            require_mfa: self.require_mfa,
+            session_ttl_seconds: self.session_ttl_seconds,
+            locale: self.locale,
+        })
+    }
+}
+
+/// Result of an update attempt inside the tenant transaction
+enum UpdateOutcome {
+    Updated(VersionedSettings),
+    VersionConflict { current_version: i32 },
+    Invalid(Vec<String>),
+}
+
+// =============================================================================
+// HANDLERS
+// =============================================================================
+
+/// Returns the settings of the current tenant
+pub async fn get_settings(
+    Extension(ctx): Extension<TenantContext>,
+) -> HandlerResponse {
+    // Already loaded by the tenant middleware
+    HandlerResponse::new(StatusCode::OK)
+        .message("Settings retrieved successfully")
+        .data(json!({
+            "settings": ctx.settings.as_ref(),
+            "version": ctx.settings_version,
+        }))
+}
+
+/// Partially updates the settings of the current tenant
+pub async fn update_settings(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<TenantContext>,
+    Json(payload): Json<UpdateSettingsRequest>,
+) -> HandlerResponse {
+    let (expected_version, patch) = payload.into_patch();
+    let tenant_id: Uuid = ctx.tenant_id;
+
+    // REPEATABLE READ: an update committed after this transaction started fails it with a
+    // serialization error instead of being overwritten. That includes a tenant's first
+    // update, where FOR UPDATE has no row to lock yet. The transaction is then retried,
+    // so the closure clones the patch instead of consuming it.
+    let options: TxOptions = TxOptions::new().isolation(IsolationLevel::RepeatableRead);
+    let result: anyhow::Result<UpdateOutcome> = state.database.with_tenant_retry(tenant_id, options, |tx| {
+        let patch: TenantSettingsPatch = patch.clone();
+        Box::pin(async move {
+            // 1. Lock the current document (Scoped Execution)
+            let row: Option<(serde_json::Value, i32)> = sqlx::query_as(
+                "SELECT settings, version FROM tenant_settings FOR UPDATE"
+            )
+            .fetch_optional(&mut **tx)
+            .await?;
+
+            let current: VersionedSettings = match row {
+                Some((document, version)) => VersionedSettings {
+                    settings: serde_json::from_value(document)?,
+                    version,
+                },
+                None => VersionedSettings::default(),
+            };
+
+            if expected_version.is_some_and(|v: i32| v != current.version) {
+                return Ok(UpdateOutcome::VersionConflict { current_version: current.version });
+            }
+
+            // 2. Merge and validate
+            let updated: TenantSettings = current.settings.apply(patch);
+            if let Err(errors) = updated.validate() {
+                return Ok(UpdateOutcome::Invalid(errors));
+            }
+
+            // 3. Persist with a bumped version
+            let version: i32 = sqlx::query_scalar(
+                r#"
+                INSERT INTO tenant_settings (tenant_id, settings, version)
+                VALUES ($1, $2, 1)
+                ON CONFLICT (tenant_id) DO UPDATE
+                SET settings = EXCLUDED.settings, version = tenant_settings.version + 1
+                RETURNING version
+                "#
+            )
+            .bind(tenant_id)
+            .bind(serde_json::to_value(&updated)?)
+            .fetch_one(&mut **tx)
+            .await?;
+
+            Ok(UpdateOutcome::Updated(VersionedSettings { settings: updated, version }))
+        })
+    }).await;
+
+    match result {
+        Ok(UpdateOutcome::Updated(saved)) => {
+            // Invalidate rather than overwrite: a slower concurrent update could otherwise
+            // leave an older version in the cache than the one committed last
+            if let Err(e) = state.cache.invalidate_tenant_settings(&tenant_id).await {
+                tracing::warn!("Failed to invalidate cached settings for tenant {}: {}", tenant_id, e);
+            }
+
+            HandlerResponse::new(StatusCode::OK)
+                .message("Settings updated successfully")
+                .data(json!({ "settings": saved.settings, "version": saved.version }))
+        }
+        Ok(UpdateOutcome::VersionConflict { current_version }) => {
+            HandlerResponse::new(StatusCode::CONFLICT)
+                .message("Settings were modified by another request")
+                .data(json!({ "error": "version_conflict", "current_version": current_version }))
+        }
+        Ok(UpdateOutcome::Invalid(errors)) => {
+            HandlerResponse::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .message("Invalid settings")
+                .data(json!({ "error": "invalid_settings", "details": errors }))
+        }
+        Err(e) => {
+            tracing::error!("Settings update failed: {}", e);
+            HandlerResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .message("Settings update failed")
+                .data(json!({ "error": e.to_string() }))
+        }
+    }
+}
+
+// =============================================================================
+// LOADING
+// =============================================================================
+
+/// Loads a tenant's settings, checking the cache before the database.
+/// Tenants that never saved settings get the defaults at version 0.
+pub async fn load_tenant_settings(state: &AppState, tenant_id: Uuid) -> anyhow::Result<VersionedSettings> {
+    match state.cache.get_tenant_settings(&tenant_id).await {
+        Ok(Some(cached)) => return Ok(cached),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to read settings for tenant {} from cache: {}", tenant_id, e),
+    }
+
+    let settings: VersionedSettings = fetch_tenant_settings(&state.database, tenant_id).await?;
+
+    if let Err(e) = state.cache.set_tenant_settings(&tenant_id, &settings).await {
+        // Don't fail request if cache fails, just log it
+        tracing::warn!("Failed to cache settings for tenant {}: {}", tenant_id, e);
+    }
+
+    Ok(settings)
+}
+
+/// Reads a tenant's settings from the database
+pub async fn fetch_tenant_settings(database: &DatabaseService, tenant_id: Uuid) -> anyhow::Result<VersionedSettings> {
+    let row: Option<(serde_json::Value, i32)> = database.with_tenant(tenant_id, |tx| Box::pin(async move {
+        sqlx::query_as("SELECT settings, version FROM tenant_settings")
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.into())
+    })).await?;
+
+    match row {
+        Some((document, version)) => Ok(VersionedSettings {
+            settings: serde_json::from_value(document)?,
+            version,
+        }),
+        None => Ok(VersionedSettings::default()),
+    }
+}
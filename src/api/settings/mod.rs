@@ -0,0 +1,4 @@
+// Tenant settings module
+
+pub mod handler;
+pub mod routes;
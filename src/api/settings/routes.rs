@@ -0,0 +1,11 @@
+// Tenant settings route definitions
+
+use axum::{routing::get, Router};
+use crate::config::state::AppState;
+use super::handler;
+
+/// Creates router with settings endpoints for the current tenant
+pub fn settings_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenant/settings", get(handler::get_settings).patch(handler::update_settings))
+}
@@ -0,0 +1,164 @@
+// Migration CLI: applies, reverts and scaffolds schema migrations outside of server startup
+//
+// Usage:
+//   migrate up [--target N] [--dry-run]     apply pending migrations (up to N)
+//   migrate down [--target N] [--dry-run]   revert the latest migration (or all above N)
+//   migrate redo [--dry-run]                revert and re-apply the latest migration
+//   migrate status                          list migrations and their state
+//   migrate new <name>                      create NNNN_<name>.sql and NNNN_<name>.down.sql
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{bail, Context, Result};
+
+use my_axum_project::config::environment::EnvironmentVariables;
+use my_axum_project::core::logging;
+use my_axum_project::database::migrations::{
+    migrate_down, migrate_redo, migrate_up, migration_status, MigrationReport, MigrationStatus, MIGRATIONS_DIR,
+};
+use my_axum_project::database::DatabaseService;
+
+const USAGE: &str = "Usage: migrate <up|down|redo|status|new> [--target N] [--dry-run] [name]";
+
+enum Command {
+    Up { target: Option<i64>, dry_run: bool },
+    Down { target: Option<i64>, dry_run: bool },
+    Redo { dry_run: bool },
+    Status,
+    New { name: String },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    logging::init_tracing();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command: Command = parse_args(&args)?;
+
+    // Scaffolding needs no database
+    if let Command::New { name } = &command {
+        return create_migration(name);
+    }
+
+    let environment: Arc<EnvironmentVariables> = Arc::new(EnvironmentVariables::load()?);
+    let database: DatabaseService = DatabaseService::new(environment);
+    let migration_pool: sqlx::PgPool = database.migration_pool().await?;
+    let pool: &sqlx::PgPool = &migration_pool;
+
+    let result: Result<()> = match command {
+        Command::Up { target, dry_run } => migrate_up(pool, target, dry_run).await.map(|r: MigrationReport| print_report(&r, dry_run)),
+        Command::Down { target, dry_run } => migrate_down(pool, target, dry_run).await.map(|r: MigrationReport| print_report(&r, dry_run)),
+        Command::Redo { dry_run } => migrate_redo(pool, dry_run).await.map(|r: MigrationReport| print_report(&r, dry_run)),
+        Command::Status => migration_status(pool).await.map(|s: Vec<MigrationStatus>| print_status(&s)),
+        Command::New { .. } => unreachable!(),
+    };
+
+    migration_pool.close().await;
+    result
+}
+
+// =============================================================================
+// ARGUMENTS
+// =============================================================================
+
+fn parse_args(args: &[String]) -> Result<Command> {
+    let Some((command, rest)) = args.split_first() else {
+        bail!("{}", USAGE);
+    };
+
+    let mut target: Option<i64> = None;
+    let mut dry_run: bool = false;
+    let mut positional: Vec<&str> = Vec::new();
+
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--target" => {
+                let value: &String = iter.next().context("--target requires a version")?;
+                target = Some(value.parse().with_context(|| format!("Invalid --target version: {}", value))?);
+            }
+            flag if flag.starts_with("--") => bail!("Unknown option {}\n{}", flag, USAGE),
+            value => positional.push(value),
+        }
+    }
+
+    let command: Command = match command.as_str() {
+        "up" => Command::Up { target, dry_run },
+        "down" => Command::Down { target, dry_run },
+        "redo" => Command::Redo { dry_run },
+        "status" => Command::Status,
+        "new" => match positional.as_slice() {
+            [name] => Command::New { name: name.to_string() },
+            _ => bail!("Usage: migrate new <name>"),
+        },
+        other => bail!("Unknown command {}\n{}", other, USAGE),
+    };
+
+    Ok(command)
+}
+
+// =============================================================================
+// COMMANDS
+// =============================================================================
+
+/// Writes an empty up/down pair with the next free version number
+fn create_migration(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        bail!("Migration name must be snake_case (a-z, 0-9, _): {}", name);
+    }
+
+    let dir: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join(MIGRATIONS_DIR);
+    let mut latest: i64 = 0;
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let file_name: String = entry?.file_name().to_string_lossy().into_owned();
+        if let Some(version) = file_name.split('_').next().and_then(|v: &str| v.parse::<i64>().ok()) {
+            latest = latest.max(version);
+        }
+    }
+
+    let stem: String = format!("{:04}_{}", latest + 1, name);
+    let up: PathBuf = dir.join(format!("{}.sql", stem));
+    let down: PathBuf = dir.join(format!("{}.down.sql", stem));
+
+    std::fs::write(&up, format!("-- {}\n\n", name.replace('_', " ")))?;
+    std::fs::write(&down, format!("-- Revert {:04}: {}\n\n", latest + 1, name.replace('_', " ")))?;
+
+    println!("Created {}", up.display());
+    println!("Created {}", down.display());
+    Ok(())
+}
+
+fn print_report(report: &MigrationReport, dry_run: bool) {
+    let prefix: &str = if dry_run { "Would apply" } else { "Applied" };
+    let revert_prefix: &str = if dry_run { "Would revert" } else { "Reverted" };
+
+    for version in &report.reverted {
+        println!("{} {}", revert_prefix, migration_name(*version));
+    }
+    let applied: &[i64] = if dry_run { &report.pending } else { &report.applied };
+    for version in applied {
+        println!("{} {}", prefix, migration_name(*version));
+    }
+    if report.reverted.is_empty() && applied.is_empty() {
+        println!("Nothing to do");
+    }
+}
+
+fn print_status(statuses: &[MigrationStatus]) {
+    println!("{:<8} {:<10} {:<33} NAME", "VERSION", "STATE", "APPLIED AT");
+    for status in statuses {
+        let applied_at: String = status.applied_at.map(|t| t.to_rfc3339()).unwrap_or_else(|| "-".to_string());
+        println!("{:<8} {:<10} {:<33} {}", status.version, status.state.as_str(), applied_at, status.name);
+    }
+}
+
+fn migration_name(version: i64) -> String {
+    my_axum_project::database::migrations::MIGRATIONS
+        .iter()
+        .find(|m| m.version == version)
+        .map(|m| m.name.to_string())
+        .unwrap_or_else(|| version.to_string())
+}
+
+// End of file: /src/bin/migrate.rs
@@ -0,0 +1,191 @@
+// Feature flag definitions and deterministic rollout evaluation
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::utils::utils::fnv1a_64;
+
+/// Global flag definition as stored in `feature_flags`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FlagDefinition {
+    pub key: String,
+    pub description: Option<String>,
+    /// Turns the percentage rollout on; tenant overrides apply regardless
+    pub enabled: bool,
+    /// Share of tenants (or users) receiving the flag, 0-100
+    pub rollout_percentage: i16,
+}
+
+/// Why a flag evaluated the way it did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    /// Flag key has no definition
+    Unknown,
+    /// Tenant-specific override
+    TenantOverride,
+    /// Flag definition is switched off
+    Disabled,
+    /// Percentage rollout bucket
+    Rollout,
+}
+
+/// Result of evaluating a single flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FlagEvaluation {
+    pub enabled: bool,
+    pub reason: FlagReason,
+}
+
+/// Flag state for one tenant: global definitions plus that tenant's overrides
+#[derive(Debug, Clone)]
+pub struct Flags {
+    tenant_id: Uuid,
+    definitions: Arc<HashMap<String, FlagDefinition>>,
+    overrides: Arc<HashMap<String, bool>>,
+}
+
+impl Flags {
+    pub fn new(tenant_id: Uuid, definitions: Vec<FlagDefinition>, overrides: HashMap<String, bool>) -> Self {
+        let definitions: HashMap<String, FlagDefinition> = definitions
+            .into_iter()
+            .map(|d: FlagDefinition| (d.key.clone(), d))
+            .collect();
+
+        Self {
+            tenant_id,
+            definitions: Arc::new(definitions),
+            overrides: Arc::new(overrides),
+        }
+    }
+
+    /// Whether the flag is on for the tenant as a whole
+    pub fn is_enabled(&self, key: &str) -> bool {
+        self.evaluate(key, None).enabled
+    }
+
+    /// Whether the flag is on for a specific user of the tenant.
+    /// Rollout buckets by tenant and user, so a tenant can be partially enabled.
+    pub fn is_enabled_for_user(&self, key: &str, user_id: Uuid) -> bool {
+        self.evaluate(key, Some(user_id)).enabled
+    }
+
+    /// Evaluates a flag: unknown → off, tenant override → as set,
+    /// disabled definition → off, otherwise the deterministic rollout bucket
+    pub fn evaluate(&self, key: &str, user_id: Option<Uuid>) -> FlagEvaluation {
+        let definition: &FlagDefinition = match self.definitions.get(key) {
+            Some(d) => d,
+            None => return FlagEvaluation { enabled: false, reason: FlagReason::Unknown },
+        };
+
+        if let Some(enabled) = self.overrides.get(key) {
+            return FlagEvaluation { enabled: *enabled, reason: FlagReason::TenantOverride };
+        }
+
+        if !definition.enabled {
+            return FlagEvaluation { enabled: false, reason: FlagReason::Disabled };
+        }
+
+        let bucket: u8 = rollout_bucket(key, self.tenant_id, user_id);
+        FlagEvaluation {
+            enabled: i16::from(bucket) < definition.rollout_percentage,
+            reason: FlagReason::Rollout,
+        }
+    }
+
+    /// Evaluates every defined flag for the tenant
+    pub fn evaluate_all(&self) -> HashMap<String, FlagEvaluation> {
+        self.definitions
+            .keys()
+            .map(|key: &String| (key.clone(), self.evaluate(key, None)))
+            .collect()
+    }
+}
+
+/// Maps (flag, tenant, user) to a stable bucket in 0..100.
+/// Uses FNV-1a so buckets never change across builds or platforms, and
+/// includes the flag key so each flag rolls out to a different population.
+pub fn rollout_bucket(key: &str, tenant_id: Uuid, user_id: Option<Uuid>) -> u8 {
+    let hash: u64 = match user_id {
+        Some(user_id) => fnv1a_64(&[key.as_bytes(), b":", tenant_id.as_bytes(), b":", user_id.as_bytes()]),
+        None => fnv1a_64(&[key.as_bytes(), b":", tenant_id.as_bytes()]),
+    };
+
+    (hash % 100) as u8
+}
+
+/// Validates a flag key: 1-100 chars of lowercase letters, digits, '_', '-' or '.'
+pub fn is_valid_flag_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 100
+        && key.chars().all(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TENANT: Uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+
+    fn definition(key: &str, enabled: bool, rollout_percentage: i16) -> FlagDefinition {
+        FlagDefinition { key: key.to_string(), description: None, enabled, rollout_percentage }
+    }
+
+    #[test]
+    fn buckets_are_stable() {
+        // Changing these moves tenants in or out of every partial rollout
+        assert_eq!(rollout_bucket("new-checkout", TENANT, None), 54);
+        assert_eq!(rollout_bucket("new-checkout", TENANT, Some(Uuid::from_u128(1))), 15);
+    }
+
+    #[test]
+    fn buckets_spread_evenly() {
+        let mut counts: [u32; 10] = [0; 10];
+        for i in 0..10_000u128 {
+            counts[usize::from(rollout_bucket("new-checkout", Uuid::from_u128(i), None) / 10)] += 1;
+        }
+        assert!(counts.iter().all(|count: &u32| (850..=1150).contains(count)), "{:?}", counts);
+    }
+
+    #[test]
+    fn rollout_percentage_is_the_share_of_buckets_enabled() {
+        let tenants: Vec<Uuid> = (0..1_000u128).map(Uuid::from_u128).collect();
+        let enabled = |percentage: i16| -> usize {
+            tenants
+                .iter()
+                .filter(|tenant_id: &&Uuid| Flags::new(**tenant_id, vec![definition("f", true, percentage)], HashMap::new()).is_enabled("f"))
+                .count()
+        };
+        assert_eq!(enabled(0), 0);
+        assert_eq!(enabled(100), tenants.len());
+        assert!((250..=350).contains(&enabled(30)), "{}", enabled(30));
+    }
+
+    #[test]
+    fn evaluation_order_is_unknown_override_disabled_rollout() {
+        let definitions: Vec<FlagDefinition> = vec![definition("off", false, 100), definition("on", true, 100)];
+        let overrides: HashMap<String, bool> = HashMap::from([("off".to_string(), true), ("unknown".to_string(), true)]);
+        let flags: Flags = Flags::new(TENANT, definitions.clone(), overrides);
+
+        assert_eq!(flags.evaluate("unknown", None), FlagEvaluation { enabled: false, reason: FlagReason::Unknown });
+        assert_eq!(flags.evaluate("off", None), FlagEvaluation { enabled: true, reason: FlagReason::TenantOverride });
+        assert_eq!(flags.evaluate("on", None), FlagEvaluation { enabled: true, reason: FlagReason::Rollout });
+
+        let flags: Flags = Flags::new(TENANT, definitions, HashMap::new());
+        assert_eq!(flags.evaluate("off", None), FlagEvaluation { enabled: false, reason: FlagReason::Disabled });
+    }
+
+    #[test]
+    fn flag_keys() {
+        for key in ["checkout", "new-checkout_v2.beta"] {
+            assert!(is_valid_flag_key(key), "{:?} was rejected", key);
+        }
+        let long: String = "a".repeat(101);
+        for key in ["", "New", "with space", "emoji-🚩", long.as_str()] {
+            assert!(!is_valid_flag_key(key), "{:?} was accepted", key);
+        }
+    }
+}
+
+// End of file: /src/config/feature_flags.rs
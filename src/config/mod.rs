@@ -1,4 +1,6 @@
 // Configuration module exports
 
 pub mod environment;
+pub mod feature_flags;
 pub mod state;
+pub mod tenant_settings;
@@ -1,59 +1,153 @@
-// Application state management with singleton pattern
+// Application state shared by every handler
 
 use std::sync::Arc;
-use once_cell::sync::Lazy;
-use crate::config::environment::EnvironmentVariables;
-use crate::database::{DatabaseService, RedisService};
+use std::time::Duration;
+use anyhow::Context;
+use sqlx::PgPool;
+use crate::api::offboarding::jobs::start_job_sweeper;
+use crate::config::environment::{CacheBackend, EnvironmentVariables};
+use crate::core::domain_verifier::{DnsOverHttpsVerifier, DomainVerifier};
+use crate::core::rate_limiter::RateLimiter;
+use crate::core::startup::{Dependency, Readiness, StartupBackoff};
+use crate::database::{is_connection_error, is_redis_connection_error, CacheService, CacheStore, DatabaseService, MemoryStore, RedisService};
 
-// AppState singleton
+/// Shared application state, handed to the router by `create_app(state)`.
+/// Build it with `AppState::from_env()` or, to inject config and fakes, `AppState::builder()`.
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub environment: Arc<EnvironmentVariables>,
     pub database: DatabaseService,
-    pub redis: RedisService,
+    pub cache: CacheService,
+    pub domain_verifier: Arc<dyn DomainVerifier>,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Set as `init_master_schema` brings up each dependency
+    pub readiness: Arc<Readiness>,
 }
 
 impl AppState {
-    /// Creates a new AppState instance (private constructor)
-    fn new() -> anyhow::Result<Self> {
+    /// Loads the environment and builds every service from it
+    pub fn from_env() -> anyhow::Result<Self> {
         let environment: EnvironmentVariables = EnvironmentVariables::load()?;
-        let environment_arc: Arc<EnvironmentVariables> = Arc::new(environment);
-        
-        // Create services
-        let database: DatabaseService = DatabaseService::new(environment_arc.clone());
-        let redis: RedisService = RedisService::new(environment_arc.clone())?;
-
-        Ok(Self {
-            environment: environment_arc,
-            database,
-            redis,
-        })
+        Self::builder().environment(environment).build()
     }
 
-    /// Returns the singleton instance
-    pub fn instance() -> &'static Self {
-        static INSTANCE: Lazy<AppState> = Lazy::new(|| {
-            AppState::new().expect("Failed to initialize AppState")
-        });
-        &INSTANCE
+    pub fn builder() -> AppStateBuilder {
+        AppStateBuilder::default()
     }
 
-    /// Initialize database with master schema and tenants table
-    pub async fn init_master_schema() -> anyhow::Result<()> {
-        let instance: &'static AppState = Self::instance();
-        
-        // Initialize both DB and Redis
-        instance.database.initialize().await?;
-        instance.redis.initialize().await?;
-        
-        tracing::info!("Services (DB + Redis) initialized successfully");
+    /// Initialize database with master schema and tenants table.
+    /// Postgres and the cache come up concurrently, each retried with backoff while unreachable
+    /// (`STARTUP_MAX_WAIT_SECS`), and are marked ready as they succeed.
+    pub async fn init_master_schema(&self) -> anyhow::Result<()> {
+        let backoff: StartupBackoff = StartupBackoff {
+            max_wait: Duration::from_secs(self.environment.startup_max_wait_secs),
+            base_delay: Duration::from_millis(self.environment.startup_retry_base_delay_ms),
+        };
+
+        let database = async {
+            backoff.retry(Dependency::Database, is_connection_error, || self.database.initialize()).await?;
+            // Fails jobs a previous process left unfinished, now and periodically
+            start_job_sweeper(self.database.get_pool()?.clone(), self.environment.export_dir.to_string());
+            self.readiness.mark_ready(Dependency::Database);
+            anyhow::Ok(())
+        };
+        let cache = async {
+            backoff.retry(Dependency::Cache, is_redis_connection_error, || self.cache.initialize()).await?;
+            self.readiness.mark_ready(Dependency::Cache);
+            anyhow::Ok(())
+        };
+        tokio::try_join!(database, cache)?;
+
+        tracing::info!("Services (DB + cache) initialized successfully");
         Ok(())
     }
 
     /// Gracefully shutdown all database connections
-    pub async fn shutdown() {
-        let instance: &'static AppState = Self::instance();
-        instance.database.shutdown().await;
-        instance.redis.shutdown().await;
+    pub async fn shutdown(&self) {
+        self.database.shutdown().await;
+        self.cache.shutdown().await;
+    }
+}
+
+// =============================================================================
+// BUILDER
+// =============================================================================
+
+/// Assembles an `AppState` from explicit parts. Anything not supplied is built
+/// from the environment, which is required.
+#[derive(Debug, Default)]
+pub struct AppStateBuilder {
+    environment: Option<Arc<EnvironmentVariables>>,
+    database: Option<DatabaseService>,
+    pool: Option<PgPool>,
+    cache: Option<Arc<dyn CacheStore>>,
+    domain_verifier: Option<Arc<dyn DomainVerifier>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl AppStateBuilder {
+    pub fn environment(mut self, environment: impl Into<Arc<EnvironmentVariables>>) -> Self {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    pub fn database(mut self, database: DatabaseService) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Runtime pool for the database service instead of one opened from the `DB_*`
+    /// variables, e.g. a pool to a test database or a lazy one to an unreachable server
+    pub fn pool(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Cache backend; defaults to the one selected by `CACHE_BACKEND`
+    pub fn cache(mut self, cache: Arc<dyn CacheStore>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn domain_verifier(mut self, domain_verifier: Arc<dyn DomainVerifier>) -> Self {
+        self.domain_verifier = Some(domain_verifier);
+        self
+    }
+
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<AppState> {
+        let environment: Arc<EnvironmentVariables> = self.environment
+            .context("AppState::builder() needs an environment")?;
+
+        let database: DatabaseService = match (self.database, self.pool) {
+            (Some(_), Some(_)) => anyhow::bail!("AppState::builder() takes a database or a pool, not both"),
+            (Some(database), None) => database,
+            (None, Some(pool)) => DatabaseService::with_pool(environment.clone(), pool),
+            (None, None) => DatabaseService::new(environment.clone()),
+        };
+        let cache_store: Arc<dyn CacheStore> = match self.cache {
+            Some(cache) => cache,
+            None => match environment.cache_backend {
+                CacheBackend::Redis => Arc::new(RedisService::new(environment.clone())?),
+                CacheBackend::Memory => Arc::new(MemoryStore::new()),
+            },
+        };
+        let domain_verifier: Arc<dyn DomainVerifier> = match self.domain_verifier {
+            Some(domain_verifier) => domain_verifier,
+            None => Arc::new(DnsOverHttpsVerifier::new(environment.dns_over_https_url.as_ref())?),
+        };
+
+        Ok(AppState {
+            environment,
+            database,
+            cache: CacheService::new(cache_store),
+            domain_verifier,
+            rate_limiter: self.rate_limiter.unwrap_or_else(|| Arc::new(RateLimiter::new())),
+            readiness: Arc::new(Readiness::new()),
+        })
     }
 }
@@ -0,0 +1,156 @@
+// Per-tenant settings with typed schema, defaults and validation
+
+use serde::{Deserialize, Serialize};
+
+/// Shortest session lifetime a tenant can configure (5 minutes)
+pub const MIN_SESSION_TTL_SECONDS: u64 = 5 * 60;
+
+/// Longest session lifetime a tenant can configure (30 days)
+pub const MAX_SESSION_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Typed view of the `tenant_settings.settings` JSONB document.
+/// Missing keys take their default, unknown keys are ignored so older
+/// documents keep loading after fields are added or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantSettings {
+    /// Whether `/auth/register` accepts new users
+    pub allow_signup: bool,
+    /// Whether users must complete MFA when signing in
+    pub require_mfa: bool,
+    /// Lifetime of sessions created at login
+    pub session_ttl_seconds: u64,
+    /// Default locale for tenant-facing content (e.g. "en", "es-AR")
+    pub locale: String,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            allow_signup: true,
+            require_mfa: false,
+            session_ttl_seconds: 24 * 60 * 60,
+            locale: "en".to_string(),
+        }
+    }
+}
+
+/// Partial update for TenantSettings; only provided fields change
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantSettingsPatch {
+    pub allow_signup: Option<bool>,
+    pub require_mfa: Option<bool>,
+    pub session_ttl_seconds: Option<u64>,
+    pub locale: Option<String>,
+}
+
+/// Settings together with the version they were read at
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedSettings {
+    pub settings: TenantSettings,
+    /// 0 when the tenant has never saved settings (defaults in effect)
+    pub version: i32,
+}
+
+impl TenantSettings {
+    /// Returns a copy of these settings with the patch applied
+    pub fn apply(&self, patch: TenantSettingsPatch) -> Self {
+        Self {
+            allow_signup: patch.allow_signup.unwrap_or(self.allow_signup),
+            require_mfa: patch.require_mfa.unwrap_or(self.require_mfa),
+            session_ttl_seconds: patch.session_ttl_seconds.unwrap_or(self.session_ttl_seconds),
+            locale: patch.locale.unwrap_or_else(|| self.locale.clone()),
+        }
+    }
+
+    /// Validates all fields, collecting every problem instead of stopping at the first
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors: Vec<String> = Vec::new();
+
+        if !(MIN_SESSION_TTL_SECONDS..=MAX_SESSION_TTL_SECONDS).contains(&self.session_ttl_seconds) {
+            errors.push(format!(
+                "session_ttl_seconds (current: {}, should be: between {} and {})",
+                self.session_ttl_seconds, MIN_SESSION_TTL_SECONDS, MAX_SESSION_TTL_SECONDS
+            ));
+        }
+
+        if !is_valid_locale(&self.locale) {
+            errors.push(format!(
+                "locale (current: \"{}\", should be: language code with optional region, e.g. \"en\" or \"es-AR\")",
+                self.locale
+            ));
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+/// Accepts `ll`, `lll`, `ll-RR` and `lll-RR` (ISO 639 language + ISO 3166 region)
+fn is_valid_locale(locale: &str) -> bool {
+    let (language, region): (&str, Option<&str>) = match locale.split_once('-') {
+        Some((l, r)) => (l, Some(r)),
+        None => (locale, None),
+    };
+
+    let language_ok: bool = (2..=3).contains(&language.len())
+        && language.chars().all(|c: char| c.is_ascii_lowercase());
+    let region_ok: bool = region.is_none_or(|r: &str| {
+        r.len() == 2 && r.chars().all(|c: char| c.is_ascii_uppercase())
+    });
+
+    language_ok && region_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_take_a_language_and_an_optional_region() {
+        for locale in ["en", "fil", "es-AR", "ast-ES"] {
+            assert!(is_valid_locale(locale), "{:?} was rejected", locale);
+        }
+        for locale in ["", "e", "engl", "EN", "en-ar", "en-ARG", "en_AR", "en-", "-AR", "en-AR-x"] {
+            assert!(!is_valid_locale(locale), "{:?} was accepted", locale);
+        }
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let settings: TenantSettings = TenantSettings {
+            session_ttl_seconds: MIN_SESSION_TTL_SECONDS - 1,
+            locale: "english".to_string(),
+            ..TenantSettings::default()
+        };
+        let errors: Vec<String> = settings.validate().unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].starts_with("session_ttl_seconds") && errors[1].starts_with("locale"), "{:?}", errors);
+    }
+
+    #[test]
+    fn session_ttl_bounds_are_inclusive() {
+        for ttl in [MIN_SESSION_TTL_SECONDS, MAX_SESSION_TTL_SECONDS] {
+            let settings: TenantSettings = TenantSettings { session_ttl_seconds: ttl, ..TenantSettings::default() };
+            assert_eq!(settings.validate(), Ok(()));
+        }
+        let settings: TenantSettings = TenantSettings { session_ttl_seconds: MAX_SESSION_TTL_SECONDS + 1, ..TenantSettings::default() };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn patches_only_change_the_given_fields() {
+        let patch: TenantSettingsPatch = serde_json::from_value(serde_json::json!({ "locale": "es-AR" })).unwrap();
+        let updated: TenantSettings = TenantSettings::default().apply(patch);
+        assert_eq!(updated, TenantSettings { locale: "es-AR".to_string(), ..TenantSettings::default() });
+    }
+
+    #[test]
+    fn documents_fill_missing_keys_and_ignore_unknown_ones() {
+        let settings: TenantSettings = serde_json::from_value(serde_json::json!({ "require_mfa": true, "theme": "dark" })).unwrap();
+        assert_eq!(settings, TenantSettings { require_mfa: true, ..TenantSettings::default() });
+        assert!(serde_json::from_value::<TenantSettingsPatch>(serde_json::json!({ "theme": "dark" })).is_err());
+    }
+}
+
+// End of file: /src/config/tenant_settings.rs
@@ -0,0 +1,145 @@
+// Custom domain ownership verification through DNS TXT records
+
+use std::time::Duration;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Label prepended to a hostname to build the TXT record name tenants must publish
+pub const VERIFICATION_RECORD_PREFIX: &str = "_axum-verification";
+
+/// Prefix of the TXT record value, followed by the domain's verification token
+pub const VERIFICATION_VALUE_PREFIX: &str = "axum-verification=";
+
+/// DNS record type code for TXT records
+const DNS_TYPE_TXT: u16 = 16;
+
+/// Returns the DNS name where the verification TXT record must be published
+pub fn verification_record_name(hostname: &str) -> String {
+    format!("{}.{}", VERIFICATION_RECORD_PREFIX, hostname)
+}
+
+/// Returns the TXT record value that proves ownership for the given token
+pub fn verification_record_value(token: &str) -> String {
+    format!("{}{}", VERIFICATION_VALUE_PREFIX, token)
+}
+
+/// Looks up TXT records for domain verification.
+/// Kept behind a trait so tests can stub DNS resolution.
+#[async_trait]
+pub trait DomainVerifier: Send + Sync + std::fmt::Debug {
+    /// Returns every TXT record value published at `name`
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>>;
+
+    /// Checks whether the expected verification record is published for `hostname`
+    async fn verify(&self, hostname: &str, token: &str) -> Result<bool> {
+        let expected: String = verification_record_value(token);
+        let records: Vec<String> = self.txt_records(&verification_record_name(hostname)).await?;
+        Ok(records.iter().any(|record: &String| record.trim() == expected))
+    }
+}
+
+/// Resolves TXT records through a DNS-over-HTTPS JSON endpoint (RFC 8484 JSON flavour)
+#[derive(Debug, Clone)]
+pub struct DnsOverHttpsVerifier {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+impl DnsOverHttpsVerifier {
+    pub fn new(endpoint: impl Into<String>) -> Result<Self> {
+        let client: reqwest::Client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .context("Failed to build DNS-over-HTTPS client")?;
+
+        Ok(Self { client, endpoint: endpoint.into() })
+    }
+}
+
+#[async_trait]
+impl DomainVerifier for DnsOverHttpsVerifier {
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>> {
+        let body: String = self.client
+            .get(&self.endpoint)
+            .query(&[("name", name), ("type", "TXT")])
+            .header(reqwest::header::ACCEPT, "application/dns-json")
+            .send()
+            .await
+            .context("DNS-over-HTTPS request failed")?
+            .error_for_status()
+            .context("DNS-over-HTTPS resolver returned an error")?
+            .text()
+            .await
+            .context("Failed to read DNS-over-HTTPS response")?;
+
+        let response: DohResponse = serde_json::from_str(&body)
+            .context("Invalid DNS-over-HTTPS response")?;
+
+        Ok(response.answer
+            .into_iter()
+            .filter(|answer: &DohAnswer| answer.record_type == DNS_TYPE_TXT)
+            .map(|answer: DohAnswer| join_txt_chunks(&answer.data))
+            .collect())
+    }
+}
+
+/// TXT data comes back as one or more quoted chunks (`"abc" "def"`); joins them into one value
+fn join_txt_chunks(data: &str) -> String {
+    let trimmed: &str = data.trim();
+    if !trimmed.starts_with('"') {
+        return trimmed.to_string();
+    }
+
+    trimmed
+        .split("\" \"")
+        .map(|chunk: &str| chunk.trim_matches('"'))
+        .collect::<Vec<&str>>()
+        .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves fixed TXT records for every name
+    #[derive(Debug)]
+    struct StaticRecords(Vec<String>);
+
+    #[async_trait]
+    impl DomainVerifier for StaticRecords {
+        async fn txt_records(&self, name: &str) -> Result<Vec<String>> {
+            assert_eq!(name, "_axum-verification.shop.example.com");
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn quoted_txt_chunks_are_joined() {
+        assert_eq!(join_txt_chunks(r#""axum-verification=" "abc""#), "axum-verification=abc");
+        assert_eq!(join_txt_chunks(r#" "single" "#), "single");
+        assert_eq!(join_txt_chunks("unquoted"), "unquoted");
+    }
+
+    #[tokio::test]
+    async fn verify_needs_the_exact_token() {
+        let verifier = StaticRecords(vec!["v=spf1 -all".to_string(), " axum-verification=abc ".to_string()]);
+        assert!(verifier.verify("shop.example.com", "abc").await.unwrap());
+        assert!(!verifier.verify("shop.example.com", "ab").await.unwrap());
+    }
+}
+
+// End of file: /src/core/domain_verifier.rs
@@ -2,7 +2,10 @@
 
 // Core application modules
 
+pub mod domain_verifier;
 pub mod logging;
+pub mod rate_limiter;
 pub mod server;
+pub mod startup;
 
 // End of file: /src/core/mod.rs
\ No newline at end of file
@@ -0,0 +1,189 @@
+// GCRA rate limiting backed by the shared cache with an in-process fallback
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::database::CacheService;
+
+/// Local buckets are pruned once the map grows past this many keys
+const LOCAL_PRUNE_THRESHOLD: usize = 10_000;
+
+/// A limit of `per_minute` requests, allowing bursts of the same size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    pub fn per_minute(per_minute: u32) -> Self {
+        Self { per_minute: per_minute.max(1) }
+    }
+
+    /// Time between requests at the sustained rate (GCRA "T")
+    fn interval_ms(&self) -> i64 {
+        (60_000 / i64::from(self.per_minute)).max(1)
+    }
+
+    /// How far ahead of schedule a caller may run (GCRA "tau")
+    fn tolerance_ms(&self) -> i64 {
+        self.interval_ms() * (i64::from(self.per_minute) - 1)
+    }
+}
+
+/// Outcome of a rate-limit check, carrying everything needed for RateLimit-* headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Milliseconds until the bucket is full again
+    pub reset_after_ms: i64,
+    /// Milliseconds until the next request would be admitted (0 when allowed)
+    pub retry_after_ms: i64,
+}
+
+impl RateLimitDecision {
+    /// Derives the decision from the bucket's theoretical arrival time after the check
+    fn from_tat(limit: RateLimit, allowed: bool, tat: i64, now: i64) -> Self {
+        let interval: i64 = limit.interval_ms();
+        let tolerance: i64 = limit.tolerance_ms();
+
+        let remaining: i64 = if allowed { (now + tolerance + interval - tat) / interval } else { 0 };
+        let retry_after_ms: i64 = if allowed { 0 } else { (tat - tolerance - now).max(0) };
+
+        Self {
+            allowed,
+            limit: limit.per_minute,
+            remaining: remaining.clamp(0, i64::from(limit.per_minute)) as u32,
+            reset_after_ms: (tat - now).max(0),
+            retry_after_ms,
+        }
+    }
+}
+
+/// Checks buckets in the cache so limits hold across instances (with the Redis
+/// backend). When the cache is unreachable each instance keeps enforcing limits
+/// from local memory.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    local: Mutex<HashMap<String, i64>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consumes one request from `bucket` if the limit allows it
+    pub async fn check(&self, cache: &CacheService, bucket: &str, limit: RateLimit) -> RateLimitDecision {
+        let now: i64 = now_ms();
+
+        match cache.rate_limit(bucket, now, limit.interval_ms(), limit.tolerance_ms()).await {
+            Ok((allowed, tat)) => RateLimitDecision::from_tat(limit, allowed, tat, now),
+            Err(e) => {
+                tracing::warn!("Rate limiter falling back to local buckets: {}", e);
+                self.check_local(bucket, limit, now)
+            }
+        }
+    }
+
+    /// Same GCRA step as `CacheStore::rate_limit`, against the in-process map
+    fn check_local(&self, bucket: &str, limit: RateLimit, now: i64) -> RateLimitDecision {
+        let mut buckets = self.local.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if buckets.len() > LOCAL_PRUNE_THRESHOLD {
+            // Expired buckets behave exactly like missing ones
+            buckets.retain(|_, tat: &mut i64| *tat > now);
+        }
+
+        let tat: i64 = buckets.get(bucket).copied().unwrap_or(now).max(now);
+        if tat - limit.tolerance_ms() > now {
+            return RateLimitDecision::from_tat(limit, false, tat, now);
+        }
+
+        let new_tat: i64 = tat + limit.interval_ms();
+        buckets.insert(bucket.to_string(), new_tat);
+        RateLimitDecision::from_tat(limit, true, new_tat, now)
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::database::MemoryStore;
+
+    #[test]
+    fn interval_and_tolerance() {
+        let limit: RateLimit = RateLimit::per_minute(60);
+        assert_eq!((limit.interval_ms(), limit.tolerance_ms()), (1_000, 59_000));
+        assert_eq!(RateLimit::per_minute(0), RateLimit::per_minute(1));
+        assert_eq!(RateLimit::per_minute(0).tolerance_ms(), 0);
+        assert_eq!(RateLimit::per_minute(120_000).interval_ms(), 1);
+    }
+
+    #[test]
+    fn a_full_burst_is_admitted_then_the_sustained_rate() {
+        let limiter: RateLimiter = RateLimiter::new();
+        let limit: RateLimit = RateLimit::per_minute(60);
+
+        for expected_remaining in (0..60).rev() {
+            let decision: RateLimitDecision = limiter.check_local("k", limit, 0);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+        let denied: RateLimitDecision = limiter.check_local("k", limit, 0);
+        assert_eq!(denied, RateLimitDecision { allowed: false, limit: 60, remaining: 0, reset_after_ms: 60_000, retry_after_ms: 1_000 });
+
+        // One interval later exactly one more request fits
+        assert!(!limiter.check_local("k", limit, 999).allowed);
+        assert!(limiter.check_local("k", limit, 1_000).allowed);
+        assert!(!limiter.check_local("k", limit, 1_000).allowed);
+    }
+
+    #[test]
+    fn an_idle_bucket_refills_completely() {
+        let limiter: RateLimiter = RateLimiter::new();
+        let limit: RateLimit = RateLimit::per_minute(10);
+        for _ in 0..10 {
+            limiter.check_local("k", limit, 0);
+        }
+        let decision: RateLimitDecision = limiter.check_local("k", limit, 600_000);
+        assert!(decision.allowed);
+        assert_eq!((decision.remaining, decision.reset_after_ms), (9, 6_000));
+    }
+
+    #[test]
+    fn buckets_are_independent() {
+        let limiter: RateLimiter = RateLimiter::new();
+        let limit: RateLimit = RateLimit::per_minute(1);
+        assert!(limiter.check_local("a", limit, 0).allowed);
+        assert!(!limiter.check_local("a", limit, 0).allowed);
+        assert!(limiter.check_local("b", limit, 0).allowed);
+    }
+
+    #[tokio::test]
+    async fn the_cache_and_the_local_fallback_agree() {
+        let cache: CacheService = CacheService::new(Arc::new(MemoryStore::new()));
+        let limiter: RateLimiter = RateLimiter::new();
+        let limit: RateLimit = RateLimit::per_minute(3);
+
+        let shared: Vec<bool> = futures_util::future::join_all((0..4).map(|_| limiter.check(&cache, "k", limit))).await
+            .iter()
+            .map(|decision: &RateLimitDecision| decision.allowed)
+            .collect();
+        let local: Vec<bool> = (0..4).map(|_| limiter.check_local("k", limit, now_ms()).allowed).collect();
+        assert_eq!(shared, [true, true, true, false]);
+        assert_eq!(local, shared);
+    }
+}
+
+// End of file: /src/core/rate_limiter.rs
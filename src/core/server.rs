@@ -12,36 +12,81 @@ use tokio::{signal, net::TcpListener};
 use listenfd::ListenFd;
 use anyhow::Result;
 
+use crate::config::environment::EnvironmentVariables;
 use crate::config::state::AppState;
-use crate::api::middleware::tenant::tenant_context_middleware;
+use crate::api::middleware::{
+    admin::admin_auth_middleware,
+    quota::monthly_quota_middleware,
+    rate_limit::{ip_rate_limit_middleware, rate_limit_middleware},
+    read_session::read_session_middleware,
+    readiness::readiness_middleware,
+    tenant::tenant_context_middleware,
+    tenant_tx::tenant_tx_middleware,
+};
 use crate::api::auth::routes::auth_routes;
+use crate::api::domains::routes::domain_routes;
+use crate::api::settings::routes::settings_routes;
+use crate::api::plans::routes::plan_routes;
+use crate::api::feature_flags::routes::{admin_feature_flag_routes, tenant_feature_flag_routes};
+use crate::api::health::routes::health_routes;
+use crate::api::offboarding::routes::admin_offboarding_routes;
 use crate::utils::{
     error_handler::handle_global_error,
     response_handler::response_wrapper
 };
 
 /// Creates and configures the application router with all middleware layers
-pub fn create_app() -> Router {
-    let state: &'static AppState = AppState::instance();
-    let env: &std::sync::Arc<crate::config::environment::EnvironmentVariables> = &state.environment;
+pub fn create_app(state: AppState) -> Router {
+    let env: std::sync::Arc<EnvironmentVariables> = state.environment.clone();
     
-    Router::new()
+    // Routes that need a resolved tenant (X-Tenant-ID header or verified custom domain)
+    let tenant_scoped: Router<AppState> = Router::new()
         .merge(auth_routes())
+        .merge(domain_routes())
+        .merge(settings_routes())
+        .merge(tenant_feature_flag_routes())
+        .merge(plan_routes())
+        // Innermost: commits or rolls back the RequestTx of the handler
+        .route_layer(from_fn(tenant_tx_middleware))
+        // Records writes so the caller's next reads skip lagging replicas
+        .route_layer(from_fn_with_state(state.clone(), read_session_middleware))
+        // Counts requests the rate limiter admitted towards the monthly quota
+        .route_layer(from_fn_with_state(state.clone(), monthly_quota_middleware))
+        // Layers run bottom-up: tenant resolution first, so limits can use the plan
+        .route_layer(from_fn_with_state(state.clone(), rate_limit_middleware))
+        .route_layer(from_fn_with_state(state.clone(), tenant_context_middleware))
+        // Outermost: a coarse per-IP limit before tenant resolution touches the database
+        .route_layer(from_fn_with_state(state.clone(), ip_rate_limit_middleware));
+
+    // Operator routes, guarded by ADMIN_API_KEY instead of a tenant
+    let admin: Router<AppState> = Router::new()
+        .merge(admin_feature_flag_routes())
+        .merge(admin_offboarding_routes())
+        .route_layer(from_fn_with_state(state.clone(), admin_auth_middleware))
+        // Outermost, so failed key attempts are limited too
+        .route_layer(from_fn_with_state(state.clone(), rate_limit_middleware));
+
+    // Everything but the probes waits for Postgres and Redis
+    let api: Router<AppState> = Router::new()
+        .merge(tenant_scoped)
+        .merge(admin)
+        .route_layer(from_fn_with_state(state.clone(), readiness_middleware));
+
+    Router::new()
+        .merge(api)
+        .merge(health_routes())
         .layer(
             ServiceBuilder::new()
                 .layer(from_fn(response_wrapper))
-                // Tenant Context (Must be before business logic but after response wrapper if wrapper doesn't need tenant info)
-                .layer(from_fn_with_state(state.clone(), tenant_context_middleware))
                 .layer(HandleErrorLayer::new(handle_global_error))
                 .layer(TimeoutLayer::new(Duration::from_secs(env.default_timeout_seconds)))
                 .layer(DefaultBodyLimit::max(env.max_request_body_size))
         )
-        .with_state(state.clone())
+        .with_state(state)
 }
 
 /// Sets up the TCP listener from environment or binds to new address
-pub async fn setup_listener() -> Result<TcpListener> {
-    let env: &std::sync::Arc<crate::config::environment::EnvironmentVariables> = &AppState::instance().environment;
+pub async fn setup_listener(env: &EnvironmentVariables) -> Result<TcpListener> {
     let mut listenfd: ListenFd = ListenFd::from_env();
     
     let listener: TcpListener = match listenfd.take_tcp_listener(0)? {
@@ -79,7 +124,4 @@ pub async fn shutdown_signal() {
         _ = ctrl_c => tracing::info!("Shutting down via Ctrl+C"),
         _ = terminate => tracing::info!("Shutting down via TERM signal"),
     }
-    
-    // Gracefully close database connections
-    AppState::shutdown().await;
 }
\ No newline at end of file
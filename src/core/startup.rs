@@ -0,0 +1,220 @@
+// Startup resilience: retrying unreachable dependencies and tracking readiness
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use tracing::{info, warn};
+
+/// Longest pause between two startup attempts
+const MAX_STARTUP_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// External services the API needs before it can serve requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency {
+    Database,
+    Cache,
+}
+
+impl Dependency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dependency::Database => "database",
+            Dependency::Cache => "cache",
+        }
+    }
+}
+
+/// Which dependencies finished initializing; reported by `/health/ready`
+#[derive(Debug, Default)]
+pub struct Readiness {
+    database: AtomicBool,
+    cache: AtomicBool,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn flag(&self, dependency: Dependency) -> &AtomicBool {
+        match dependency {
+            Dependency::Database => &self.database,
+            Dependency::Cache => &self.cache,
+        }
+    }
+
+    pub fn mark_ready(&self, dependency: Dependency) {
+        self.flag(dependency).store(true, Ordering::Release);
+    }
+
+    pub fn is_ready(&self, dependency: Dependency) -> bool {
+        self.flag(dependency).load(Ordering::Acquire)
+    }
+
+    pub fn all_ready(&self) -> bool {
+        self.is_ready(Dependency::Database) && self.is_ready(Dependency::Cache)
+    }
+}
+
+/// Bounded exponential backoff for connecting to dependencies at startup
+#[derive(Debug, Clone, Copy)]
+pub struct StartupBackoff {
+    /// Total time to keep retrying before giving up (0 tries once)
+    pub max_wait: Duration,
+    pub base_delay: Duration,
+}
+
+impl StartupBackoff {
+    /// Runs `attempt` until it succeeds, retrying errors for which `is_transient`
+    /// holds with doubling delays (capped at 10s) until `max_wait` has passed.
+    /// Other errors are returned immediately.
+    pub async fn retry<F, Fut>(&self, dependency: Dependency, is_transient: fn(&anyhow::Error) -> bool, mut attempt: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let started: Instant = Instant::now();
+        let mut attempts: u32 = 0;
+
+        loop {
+            attempts += 1;
+            let error: anyhow::Error = match attempt().await {
+                Ok(()) => {
+                    if attempts > 1 {
+                        info!("{} reachable after {} attempts ({:?})", dependency.as_str(), attempts, started.elapsed());
+                    }
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+
+            if !is_transient(&error) {
+                return Err(error.context(format!("{} initialization failed", dependency.as_str())));
+            }
+
+            let delay: Duration = self.delay(attempts);
+            if started.elapsed() + delay > self.max_wait {
+                return Err(error.context(format!(
+                    "{} not reachable after {} attempts ({:?})",
+                    dependency.as_str(), attempts, started.elapsed()
+                )));
+            }
+
+            warn!("{} not reachable (attempt {}), retrying in {:?}: {:#}", dependency.as_str(), attempts, delay, error);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Pause after the given failed attempt (1-based)
+    fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(MAX_STARTUP_RETRY_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    fn backoff(max_wait_ms: u64, base_delay_ms: u64) -> StartupBackoff {
+        StartupBackoff { max_wait: Duration::from_millis(max_wait_ms), base_delay: Duration::from_millis(base_delay_ms) }
+    }
+
+    fn always_transient(_: &anyhow::Error) -> bool {
+        true
+    }
+
+    fn never_transient(_: &anyhow::Error) -> bool {
+        false
+    }
+
+    #[test]
+    fn delays_double_from_the_base_delay() {
+        let backoff: StartupBackoff = backoff(60_000, 500);
+        let delays: Vec<u64> = (1..=5).map(|attempt: u32| backoff.delay(attempt).as_millis() as u64).collect();
+        assert_eq!(delays, [500, 1_000, 2_000, 4_000, 8_000]);
+    }
+
+    #[test]
+    fn delays_are_capped() {
+        let backoff: StartupBackoff = backoff(60_000, 500);
+        assert_eq!(backoff.delay(6), MAX_STARTUP_RETRY_DELAY);
+        assert_eq!(backoff.delay(u32::MAX), MAX_STARTUP_RETRY_DELAY);
+        assert_eq!(StartupBackoff { base_delay: Duration::MAX, ..backoff }.delay(20), MAX_STARTUP_RETRY_DELAY);
+    }
+
+    #[test]
+    fn readiness_needs_every_dependency() {
+        let readiness: Readiness = Readiness::new();
+        assert!(!readiness.all_ready());
+        readiness.mark_ready(Dependency::Database);
+        assert!(readiness.is_ready(Dependency::Database));
+        assert!(!readiness.is_ready(Dependency::Cache));
+        assert!(!readiness.all_ready());
+        readiness.mark_ready(Dependency::Cache);
+        assert!(readiness.all_ready());
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried_until_success() {
+        let calls: AtomicU32 = AtomicU32::new(0);
+        let result: Result<()> = backoff(5_000, 1)
+            .retry(Dependency::Database, always_transient, || async {
+                match calls.fetch_add(1, Ordering::Relaxed) {
+                    0 | 1 => anyhow::bail!("connection refused"),
+                    _ => Ok(()),
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_not_retried() {
+        let calls: AtomicU32 = AtomicU32::new(0);
+        let error: anyhow::Error = backoff(5_000, 1)
+            .retry(Dependency::Cache, never_transient, || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                anyhow::bail!("authentication failed")
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(error.to_string(), "cache initialization failed");
+        assert_eq!(error.root_cause().to_string(), "authentication failed");
+    }
+
+    #[tokio::test]
+    async fn a_zero_max_wait_tries_once() {
+        let calls: AtomicU32 = AtomicU32::new(0);
+        let error: anyhow::Error = backoff(0, 1)
+            .retry(Dependency::Database, always_transient, || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                anyhow::bail!("connection refused")
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(error.to_string().starts_with("database not reachable after 1 attempts"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn retrying_stops_before_the_next_delay_would_pass_max_wait() {
+        let calls: AtomicU32 = AtomicU32::new(0);
+        // Delays of 5 to 160 ms add up to 315 ms; the next one (320 ms) would pass 500 ms
+        let result: Result<()> = backoff(500, 5)
+            .retry(Dependency::Database, always_transient, || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                anyhow::bail!("connection refused")
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 7);
+    }
+}
+
+// End of file: /src/core/startup.rs
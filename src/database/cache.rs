@@ -0,0 +1,367 @@
+// =============================================================================
+// CACHE SERVICE - Typed caches on top of a CacheStore
+// =============================================================================
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::plans::quota::PlanLimits;
+use crate::config::environment::CacheFailurePolicy;
+use crate::config::feature_flags::FlagDefinition;
+use crate::config::tenant_settings::VersionedSettings;
+use crate::database::cache_store::{CacheStore, ConnectionStats};
+use crate::database::circuit_breaker::is_circuit_open;
+
+/// Every cache key is built here
+mod keys {
+    use uuid::Uuid;
+
+    pub fn tenant(tenant_id: &Uuid) -> String {
+        format!("tenant:{}", tenant_id)
+    }
+
+    /// Matches every key of a tenant (existence marker, settings, plan, flags, counters)
+    pub fn tenant_pattern(tenant_id: &Uuid) -> String {
+        format!("tenant:{}*", tenant_id)
+    }
+
+    pub fn tenant_settings(tenant_id: &Uuid) -> String {
+        format!("tenant:{}:settings", tenant_id)
+    }
+
+    pub fn tenant_plan(tenant_id: &Uuid) -> String {
+        format!("tenant:{}:plan", tenant_id)
+    }
+
+    pub fn tenant_flag_overrides(tenant_id: &Uuid) -> String {
+        format!("tenant:{}:feature_flags", tenant_id)
+    }
+
+    pub fn monthly_requests(tenant_id: &Uuid, period: &str) -> String {
+        format!("tenant:{}:requests:{}", tenant_id, period)
+    }
+
+    pub const FEATURE_FLAGS: &str = "feature_flags:definitions";
+
+    pub fn domain(hostname: &str) -> String {
+        format!("domain:{}", hostname)
+    }
+
+    pub fn rate_limit(bucket: &str) -> String {
+        format!("ratelimit:{}", bucket)
+    }
+
+    pub fn session(token: &str) -> String {
+        format!("session:{}", token)
+    }
+
+    pub const SESSION_PATTERN: &str = "session:*";
+}
+
+/// The policy is configuration (`config::environment`); applying it lives with the caches
+impl CacheFailurePolicy {
+    /// Applies the policy to a cache read: failing open turns an error into a miss
+    /// (`T::default()`), failing closed passes it on
+    pub fn read<T: Default>(self, use_case: &str, result: Result<T>) -> Result<T> {
+        match (result, self) {
+            (Ok(value), _) => Ok(value),
+            (Err(e), CacheFailurePolicy::FailOpen) => {
+                // The breaker already logged the outage when it opened
+                if is_circuit_open(&e) {
+                    tracing::debug!("Cache circuit open for {}, falling back to the database", use_case);
+                } else {
+                    tracing::warn!("Cache unavailable for {}, falling back to the database: {}", use_case, e);
+                }
+                Ok(T::default())
+            }
+            (Err(e), CacheFailurePolicy::FailClosed) => Err(e),
+        }
+    }
+}
+
+/// Application caches over the configured `CacheStore`. Callers treat errors as
+/// misses where the database can answer instead, see `CacheFailurePolicy`.
+#[derive(Debug, Clone)]
+pub struct CacheService {
+    store: Arc<dyn CacheStore>,
+}
+
+impl CacheService {
+    pub fn new(store: Arc<dyn CacheStore>) -> Self {
+        Self { store }
+    }
+
+    /// The underlying store, e.g. for pub/sub
+    pub fn store(&self) -> &Arc<dyn CacheStore> {
+        &self.store
+    }
+
+    /// Connection counters of the store, None for the in-memory backend
+    pub fn connection_stats(&self) -> Option<ConnectionStats> {
+        self.store.connection_stats()
+    }
+
+    pub async fn initialize(&self) -> Result<()> {
+        self.store.initialize().await
+    }
+
+    pub async fn shutdown(&self) {
+        self.store.shutdown().await;
+    }
+
+    /// Reads and deserializes a JSON value; a payload that no longer deserializes is a miss
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let value: Option<String> = self.store.get(key).await?;
+        Ok(value.and_then(|v: String| serde_json::from_str(&v).ok()))
+    }
+
+    /// Serializes and stores a JSON value with an expiration in seconds
+    async fn set_json<T: Serialize>(&self, key: &str, value: &T, ttl_seconds: u64) -> Result<()> {
+        let payload: String = serde_json::to_string(value)?;
+        self.store.set(key, &payload, Some(Duration::from_secs(ttl_seconds))).await
+    }
+
+    async fn delete_key(&self, key: &str) -> Result<()> {
+        self.store.delete(&[key.to_string()]).await?;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// TENANT CACHE
+// =============================================================================
+
+impl CacheService {
+    /// Checks if a tenant exists in the cache
+    pub async fn tenant_exists(&self, tenant_id: &Uuid) -> Result<bool> {
+        self.store.exists(&keys::tenant(tenant_id)).await
+    }
+
+    /// Caches a tenant as valid (24 hours expiration)
+    pub async fn set_tenant(&self, tenant_id: &Uuid) -> Result<()> {
+        // Value doesn't matter much, just existence
+        self.store.set(&keys::tenant(tenant_id), "valid", Some(Duration::from_secs(86400))).await
+    }
+}
+
+// =============================================================================
+// CUSTOM DOMAIN CACHE
+// =============================================================================
+
+/// Cached value marking a hostname that does not resolve to any tenant
+const UNKNOWN_DOMAIN_MARKER: &str = "none";
+
+/// Lookup result for a hostname in the domain cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachedDomain {
+    /// Hostname resolves to this tenant
+    Tenant(Uuid),
+    /// Hostname is known not to belong to any tenant
+    Unknown,
+}
+
+impl CacheService {
+    /// Returns the cached resolution for a hostname, or None on a cache miss
+    pub async fn get_domain_tenant(&self, hostname: &str) -> Result<Option<CachedDomain>> {
+        let value: Option<String> = self.store.get(&keys::domain(hostname)).await?;
+
+        Ok(value.map(|v: String| match Uuid::parse_str(&v) {
+            Ok(tenant_id) => CachedDomain::Tenant(tenant_id),
+            Err(_) => CachedDomain::Unknown,
+        }))
+    }
+
+    /// Caches the resolution of a hostname.
+    /// Unknown hostnames are cached briefly so a newly verified domain is picked up quickly.
+    pub async fn set_domain_tenant(&self, hostname: &str, resolution: CachedDomain) -> Result<()> {
+        let (value, ttl): (String, u64) = match resolution {
+            CachedDomain::Tenant(tenant_id) => (tenant_id.to_string(), 3600), // 1 hour
+            CachedDomain::Unknown => (UNKNOWN_DOMAIN_MARKER.to_string(), 60), // 1 minute
+        };
+
+        self.store.set(&keys::domain(hostname), &value, Some(Duration::from_secs(ttl))).await
+    }
+
+    /// Removes a hostname from the domain cache (after verification or deletion)
+    pub async fn invalidate_domain(&self, hostname: &str) -> Result<()> {
+        self.delete_key(&keys::domain(hostname)).await
+    }
+}
+
+// =============================================================================
+// TENANT SETTINGS CACHE
+// =============================================================================
+
+impl CacheService {
+    /// Returns the cached settings of a tenant, or None on a cache miss
+    pub async fn get_tenant_settings(&self, tenant_id: &Uuid) -> Result<Option<VersionedSettings>> {
+        self.get_json(&keys::tenant_settings(tenant_id)).await
+    }
+
+    /// Caches the settings of a tenant (1 hour expiration)
+    pub async fn set_tenant_settings(&self, tenant_id: &Uuid, settings: &VersionedSettings) -> Result<()> {
+        self.set_json(&keys::tenant_settings(tenant_id), settings, 3600).await
+    }
+
+    /// Drops the cached settings of a tenant; the next read repopulates them from the database
+    pub async fn invalidate_tenant_settings(&self, tenant_id: &Uuid) -> Result<()> {
+        self.delete_key(&keys::tenant_settings(tenant_id)).await
+    }
+}
+
+// =============================================================================
+// FEATURE FLAG CACHE
+// =============================================================================
+
+/// Flags change rarely but must propagate quickly after an admin toggle,
+/// so entries are invalidated on write and expire as a safety net
+const FEATURE_FLAG_TTL_SECONDS: u64 = 300;
+
+impl CacheService {
+    /// Returns the cached global flag definitions, or None on a cache miss
+    pub async fn get_feature_flags(&self) -> Result<Option<Vec<FlagDefinition>>> {
+        self.get_json(keys::FEATURE_FLAGS).await
+    }
+
+    /// Caches the global flag definitions
+    pub async fn set_feature_flags(&self, definitions: &[FlagDefinition]) -> Result<()> {
+        self.set_json(keys::FEATURE_FLAGS, &definitions, FEATURE_FLAG_TTL_SECONDS).await
+    }
+
+    /// Drops the cached global flag definitions
+    pub async fn invalidate_feature_flags(&self) -> Result<()> {
+        self.delete_key(keys::FEATURE_FLAGS).await
+    }
+
+    /// Returns the cached flag overrides of a tenant, or None on a cache miss
+    pub async fn get_tenant_flag_overrides(&self, tenant_id: &Uuid) -> Result<Option<HashMap<String, bool>>> {
+        self.get_json(&keys::tenant_flag_overrides(tenant_id)).await
+    }
+
+    /// Caches the flag overrides of a tenant
+    pub async fn set_tenant_flag_overrides(&self, tenant_id: &Uuid, overrides: &HashMap<String, bool>) -> Result<()> {
+        self.set_json(&keys::tenant_flag_overrides(tenant_id), overrides, FEATURE_FLAG_TTL_SECONDS).await
+    }
+
+    /// Drops the cached flag overrides of a tenant
+    pub async fn invalidate_tenant_flag_overrides(&self, tenant_id: &Uuid) -> Result<()> {
+        self.delete_key(&keys::tenant_flag_overrides(tenant_id)).await
+    }
+}
+
+// =============================================================================
+// USAGE COUNTERS
+// =============================================================================
+
+/// Monthly counters outlive their month slightly so the last day can still be read
+const MONTHLY_COUNTER_TTL_SECONDS: u64 = 40 * 24 * 60 * 60;
+
+impl CacheService {
+    /// Increments the request counter of a tenant for a month ("YYYY-MM") and returns the new value
+    pub async fn incr_monthly_requests(&self, tenant_id: &Uuid, period: &str) -> Result<i64> {
+        self.store
+            .incr(&keys::monthly_requests(tenant_id, period), Duration::from_secs(MONTHLY_COUNTER_TTL_SECONDS))
+            .await
+    }
+
+    /// Returns the request counter of a tenant for a month ("YYYY-MM")
+    pub async fn get_monthly_requests(&self, tenant_id: &Uuid, period: &str) -> Result<i64> {
+        let count: Option<String> = self.store.get(&keys::monthly_requests(tenant_id, period)).await?;
+        Ok(count.and_then(|c: String| c.parse::<i64>().ok()).unwrap_or(0))
+    }
+}
+
+// =============================================================================
+// RATE LIMITING
+// =============================================================================
+
+impl CacheService {
+    /// Runs one GCRA step for a rate-limit bucket atomically in the store.
+    /// Times are in milliseconds; returns (allowed, theoretical arrival time).
+    pub async fn rate_limit(&self, bucket: &str, now_ms: i64, interval_ms: i64, tolerance_ms: i64) -> Result<(bool, i64)> {
+        self.store.rate_limit(&keys::rate_limit(bucket), now_ms, interval_ms, tolerance_ms).await
+    }
+}
+
+// =============================================================================
+// TENANT PLAN CACHE
+// =============================================================================
+
+impl CacheService {
+    /// Returns the cached plan of a tenant, or None on a cache miss
+    pub async fn get_tenant_plan(&self, tenant_id: &Uuid) -> Result<Option<PlanLimits>> {
+        self.get_json(&keys::tenant_plan(tenant_id)).await
+    }
+
+    /// Caches the plan of a tenant (5 minutes expiration)
+    pub async fn set_tenant_plan(&self, tenant_id: &Uuid, plan: &PlanLimits) -> Result<()> {
+        self.set_json(&keys::tenant_plan(tenant_id), plan, 300).await
+    }
+}
+
+// =============================================================================
+// SESSIONS
+// =============================================================================
+
+/// A logged-in user, stored under its opaque session token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub user_id: Uuid,
+    pub tenant_id: Uuid,
+    pub email: String,
+}
+
+impl CacheService {
+    /// Stores a session for `ttl_seconds`
+    pub async fn create_session(&self, token: &str, session: &Session, ttl_seconds: u64) -> Result<()> {
+        self.set_json(&keys::session(token), session, ttl_seconds).await
+    }
+
+    /// Returns the session of a token, or None when unknown or expired
+    pub async fn get_session(&self, token: &str) -> Result<Option<Session>> {
+        self.get_json(&keys::session(token)).await
+    }
+}
+
+// =============================================================================
+// TENANT PURGE
+// =============================================================================
+
+impl CacheService {
+    /// Deletes everything cached for a tenant: `tenant:{id}*` keys, its rate-limit
+    /// bucket, the given custom domains and every session issued for the tenant.
+    /// Returns the number of keys removed.
+    pub async fn purge_tenant(&self, tenant_id: &Uuid, hostnames: &[String]) -> Result<u64> {
+        let mut removed: u64 = 0;
+
+        // 1. Tenant keys (existence marker, settings, plan, flags, usage counters)
+        let mut tenant_keys: Vec<String> = self.store.scan(&keys::tenant_pattern(tenant_id)).await?;
+        tenant_keys.push(keys::rate_limit(&format!("tenant:{}", tenant_id)));
+        tenant_keys.extend(hostnames.iter().map(|h: &String| keys::domain(h)));
+        removed += self.store.delete(&tenant_keys).await?;
+
+        // 2. Sessions are keyed by token only, so match on the stored tenant_id
+        let session_keys: Vec<String> = self.store.scan(keys::SESSION_PATTERN).await?;
+        let values: Vec<Option<String>> = self.store.get_many(&session_keys).await?;
+        let owned: Vec<String> = session_keys
+            .into_iter()
+            .zip(values)
+            .filter(|(_, value): &(String, Option<String>)| {
+                value
+                    .as_deref()
+                    .and_then(|v: &str| serde_json::from_str::<Session>(v).ok())
+                    .is_some_and(|session: Session| session.tenant_id == *tenant_id)
+            })
+            .map(|(key, _): (String, Option<String>)| key)
+            .collect();
+        removed += self.store.delete(&owned).await?;
+
+        Ok(removed)
+    }
+}
+
+// End of file: /src/database/cache.rs
@@ -0,0 +1,246 @@
+// =============================================================================
+// CACHE STORE - Backend-neutral key/value operations
+// =============================================================================
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use crate::database::circuit_breaker::CircuitState;
+
+/// Messages published on a channel, see `CacheStore::subscribe`
+pub type CacheSubscription = mpsc::UnboundedReceiver<String>;
+
+/// Connection health of a networked store, see `CacheStore::connection_stats`.
+/// Counters are totals since the process started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConnectionStats {
+    /// The last command (or connection attempt) succeeded
+    pub connected: bool,
+    pub commands: u64,
+    pub failures: u64,
+    /// Commands that exceeded the command timeout
+    pub timeouts: u64,
+    /// Dropped connections, each re-established in the background
+    pub reconnects: u64,
+    /// Circuit breaker in front of the connection; calls fail fast while it is open
+    pub circuit: CircuitState,
+    /// Times the circuit breaker opened
+    pub circuit_opened: u64,
+}
+
+/// Raw operations the caches are built on, implemented by Redis (`RedisService`)
+/// and by the in-process `MemoryStore` (`CACHE_BACKEND`).
+/// Keys are formatted by `CacheService` only; stores never build keys themselves.
+#[async_trait]
+pub trait CacheStore: Send + Sync + std::fmt::Debug {
+    /// Checks the backend is reachable (called at startup)
+    async fn initialize(&self) -> Result<()>;
+
+    async fn shutdown(&self);
+
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+
+    /// Values of several keys at once, in order (None for misses)
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>>;
+
+    /// Stores a value, expiring after `ttl` when given
+    async fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()>;
+
+    /// Deletes keys, returning how many existed
+    async fn delete(&self, keys: &[String]) -> Result<u64>;
+
+    async fn exists(&self, key: &str) -> Result<bool>;
+
+    /// Increments an integer counter (missing counts as 0), resets its expiry to `ttl`
+    /// and returns the new value
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<i64>;
+
+    /// All keys matching a glob pattern where `*` matches any run of characters
+    async fn scan(&self, pattern: &str) -> Result<Vec<String>>;
+
+    /// One atomic GCRA step on the theoretical arrival time stored at `key`: admits the
+    /// request and advances it by `interval_ms`, or rejects it untouched.
+    /// Times are in milliseconds; returns (allowed, theoretical arrival time).
+    async fn rate_limit(&self, key: &str, now_ms: i64, interval_ms: i64, tolerance_ms: i64) -> Result<(bool, i64)>;
+
+    async fn publish(&self, channel: &str, message: &str) -> Result<()>;
+
+    /// Receives messages published on `channel` until the receiver is dropped
+    async fn subscribe(&self, channel: &str) -> Result<CacheSubscription>;
+
+    /// Connection counters for monitoring; None for stores without a connection
+    fn connection_stats(&self) -> Option<ConnectionStats> {
+        None
+    }
+}
+
+// =============================================================================
+// IN-MEMORY STORE
+// =============================================================================
+
+/// Expired entries are pruned once the map grows past this size
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug)]
+struct MemoryEntry {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl MemoryEntry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.is_none_or(|expires_at: Instant| expires_at > now)
+    }
+}
+
+/// Process-local `CacheStore` for development and tests. Nothing is shared between
+/// instances, so sessions, rate limits and invalidations stay per process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, MemoryEntry>>,
+    channels: Mutex<HashMap<String, Vec<mpsc::UnboundedSender<String>>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, MemoryEntry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn live_value(entries: &HashMap<String, MemoryEntry>, key: &str, now: Instant) -> Option<String> {
+        entries.get(key).filter(|entry: &&MemoryEntry| entry.is_live(now)).map(|entry: &MemoryEntry| entry.value.clone())
+    }
+
+    fn insert(entries: &mut HashMap<String, MemoryEntry>, key: &str, value: String, expires_at: Option<Instant>, now: Instant) {
+        if entries.len() >= MEMORY_PRUNE_THRESHOLD {
+            entries.retain(|_, entry: &mut MemoryEntry| entry.is_live(now));
+        }
+        entries.insert(key.to_string(), MemoryEntry { value, expires_at });
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn shutdown(&self) {
+        self.entries().clear();
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(Self::live_value(&self.entries(), key, Instant::now()))
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<String>>> {
+        let entries = self.entries();
+        let now: Instant = Instant::now();
+        Ok(keys.iter().map(|key: &String| Self::live_value(&entries, key, now)).collect())
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()> {
+        let now: Instant = Instant::now();
+        Self::insert(&mut self.entries(), key, value.to_string(), ttl.map(|ttl: Duration| now + ttl), now);
+        Ok(())
+    }
+
+    async fn delete(&self, keys: &[String]) -> Result<u64> {
+        let mut entries = self.entries();
+        let now: Instant = Instant::now();
+        let removed: usize = keys
+            .iter()
+            .filter_map(|key: &String| entries.remove(key))
+            .filter(|entry: &MemoryEntry| entry.is_live(now))
+            .count();
+        Ok(removed as u64)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(Self::live_value(&self.entries(), key, Instant::now()).is_some())
+    }
+
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<i64> {
+        let mut entries = self.entries();
+        let now: Instant = Instant::now();
+        let current: i64 = match Self::live_value(&entries, key, now) {
+            Some(value) => value.parse::<i64>().map_err(|_| anyhow::anyhow!("{} does not hold an integer", key))?,
+            None => 0,
+        };
+        Self::insert(&mut entries, key, (current + 1).to_string(), Some(now + ttl), now);
+        Ok(current + 1)
+    }
+
+    async fn scan(&self, pattern: &str) -> Result<Vec<String>> {
+        let now: Instant = Instant::now();
+        Ok(self.entries()
+            .iter()
+            .filter(|(key, entry): &(&String, &MemoryEntry)| entry.is_live(now) && glob_matches(pattern, key))
+            .map(|(key, _): (&String, &MemoryEntry)| key.clone())
+            .collect())
+    }
+
+    async fn rate_limit(&self, key: &str, now_ms: i64, interval_ms: i64, tolerance_ms: i64) -> Result<(bool, i64)> {
+        let mut entries = self.entries();
+        let now: Instant = Instant::now();
+        let tat: i64 = Self::live_value(&entries, key, now)
+            .and_then(|value: String| value.parse::<i64>().ok())
+            .unwrap_or(now_ms)
+            .max(now_ms);
+        if tat - tolerance_ms > now_ms {
+            return Ok((false, tat));
+        }
+
+        let new_tat: i64 = tat + interval_ms;
+        let ttl: Duration = Duration::from_millis((new_tat - now_ms).max(0) as u64);
+        Self::insert(&mut entries, key, new_tat.to_string(), Some(now + ttl), now);
+        Ok((true, new_tat))
+    }
+
+    async fn publish(&self, channel: &str, message: &str) -> Result<()> {
+        let mut channels = self.channels.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(subscribers) = channels.get_mut(channel) {
+            // Dropped receivers unsubscribe
+            subscribers.retain(|subscriber: &mpsc::UnboundedSender<String>| subscriber.send(message.to_string()).is_ok());
+        }
+        Ok(())
+    }
+
+    async fn subscribe(&self, channel: &str) -> Result<CacheSubscription> {
+        let (sender, receiver) = mpsc::unbounded_channel::<String>();
+        self.channels
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(channel.to_string())
+            .or_default()
+            .push(sender);
+        Ok(receiver)
+    }
+}
+
+/// Matches `key` against a pattern where `*` matches any run of characters (Redis
+/// MATCH semantics without `?`, `[...]` and escapes, which the caches never use)
+fn glob_matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first: &str = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else { return false };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else { return rest.is_empty() };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+// End of file: /src/database/cache_store.rs
@@ -0,0 +1,130 @@
+// Circuit breaker for the cache connection: stops calling Redis after repeated
+// connection failures and lets a single trial call through once the cooldown is over
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// State of a `CircuitBreaker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls fail fast with `CircuitOpen` until the cooldown is over
+    Open,
+    /// One trial call goes through; its outcome closes or reopens the circuit
+    HalfOpen,
+}
+
+/// Error of a call rejected without being attempted, see `is_circuit_open`
+#[derive(Debug)]
+pub struct CircuitOpen;
+
+impl std::fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cache circuit breaker is open")
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// True if the call was rejected by an open circuit breaker
+pub fn is_circuit_open(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| e.is::<CircuitOpen>())
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// When the circuit opened, or when the half-open trial call started
+    since: Instant,
+}
+
+/// Opens after `failure_threshold` consecutive failures. After `cooldown` one trial call
+/// is let through: success closes the circuit, failure opens it for another cooldown.
+/// A trial that never reports back (e.g. a cancelled request) is replaced after a cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    circuit: Mutex<Circuit>,
+    /// Times the circuit opened since the process started
+    opened: AtomicU64,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            circuit: Mutex::new(Circuit {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+            }),
+            opened: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether a call may go ahead; every permitted call must report `on_success` or `on_failure`
+    pub fn try_acquire(&self) -> bool {
+        let mut circuit = self.lock();
+        match circuit.state {
+            CircuitState::Closed => true,
+            CircuitState::Open | CircuitState::HalfOpen if circuit.since.elapsed() >= self.cooldown => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.since = Instant::now();
+                true
+            }
+            CircuitState::Open | CircuitState::HalfOpen => false,
+        }
+    }
+
+    /// The backend answered (even with an error of its own)
+    pub fn on_success(&self) {
+        let mut circuit = self.lock();
+        if circuit.state != CircuitState::Closed {
+            info!("Cache circuit breaker closed");
+        }
+        circuit.state = CircuitState::Closed;
+        circuit.consecutive_failures = 0;
+    }
+
+    /// The backend could not be reached
+    pub fn on_failure(&self) {
+        let mut circuit = self.lock();
+        circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+
+        let trips: bool = match circuit.state {
+            CircuitState::Closed => circuit.consecutive_failures >= self.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if trips {
+            warn!(
+                "Cache circuit breaker opened after {} consecutive failures, retrying in {:?}",
+                circuit.consecutive_failures, self.cooldown
+            );
+            circuit.state = CircuitState::Open;
+            circuit.since = Instant::now();
+            self.opened.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Times the circuit opened since the process started
+    pub fn opened_count(&self) -> u64 {
+        self.opened.load(Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Circuit> {
+        self.circuit.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,523 @@
+// Versioned, checksummed, reversible schema migrations
+
+use std::collections::HashMap;
+use std::time::Instant;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Postgres};
+use tracing::{info, warn};
+
+/// Advisory lock held while migrating, so replicas starting together run migrations once
+const MIGRATION_LOCK_KEY: i64 = 0x006d_6967_7261_7465; // "migrate"
+
+/// Directory holding `NNNN_name.sql` and optional `NNNN_name.down.sql` files
+pub const MIGRATIONS_DIR: &str = "src/database/sql/migrations";
+
+/// A migration compiled into the binary from `sql/migrations/<name>.sql`
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+    /// Contents of `<name>.down.sql`; None when the migration is irreversible
+    pub down: Option<&'static str>,
+}
+
+impl Migration {
+    /// SHA-256 of the SQL, recorded on apply to detect later edits
+    pub fn checksum(&self) -> String {
+        format!("{:x}", Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+/// Every migration in version order, generated by build.rs from MIGRATIONS_DIR.
+/// Applied migrations must never be edited; add a new file instead.
+pub const MIGRATIONS: &[Migration] = include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// Row of `schema_migrations`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub execution_ms: i64,
+}
+
+/// What a migration run did (or would do, in dry-run mode)
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Versions applied by this run
+    pub applied: Vec<i64>,
+    /// Versions reverted by this run
+    pub reverted: Vec<i64>,
+    /// Versions not yet applied when the run started
+    pub pending: Vec<i64>,
+}
+
+/// State of one migration, as shown by `migrate status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file changed since
+    Modified,
+    /// Applied by a build that has a migration this one does not know
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+// =============================================================================
+// COMMANDS
+// =============================================================================
+
+/// Applies pending migrations up to `target` (all when None) under the migration lock.
+/// With `dry_run` the migrations are only reported; nothing is written.
+pub async fn migrate_up(pool: &PgPool, target: Option<i64>, dry_run: bool) -> Result<MigrationReport> {
+    let mut conn: PoolConnection<Postgres> = acquire_lock(pool).await?;
+    let result: Result<MigrationReport> = up_locked(&mut conn, target, dry_run).await;
+    release_lock(conn).await;
+    result
+}
+
+/// Reverts applied migrations newer than `target`; only the latest one when None
+pub async fn migrate_down(pool: &PgPool, target: Option<i64>, dry_run: bool) -> Result<MigrationReport> {
+    let mut conn: PoolConnection<Postgres> = acquire_lock(pool).await?;
+    let result: Result<MigrationReport> = down_locked(&mut conn, target, dry_run).await;
+    release_lock(conn).await;
+    result
+}
+
+/// Reverts the latest applied migration and applies it again
+pub async fn migrate_redo(pool: &PgPool, dry_run: bool) -> Result<MigrationReport> {
+    let mut conn: PoolConnection<Postgres> = acquire_lock(pool).await?;
+
+    let result: Result<MigrationReport> = async {
+        let mut report: MigrationReport = down_locked(&mut conn, None, dry_run).await?;
+        if let Some(version) = report.reverted.first().copied() {
+            if dry_run {
+                // Nothing was reverted, so the re-apply can only be reported
+                report.pending = vec![version];
+            } else {
+                let up: MigrationReport = up_locked(&mut conn, Some(version), false).await?;
+                report.applied = up.applied;
+                report.pending = up.pending;
+            }
+        }
+        Ok(report)
+    }.await;
+
+    release_lock(conn).await;
+    result
+}
+
+/// Lists known and applied migrations with their state
+pub async fn migration_status(pool: &PgPool) -> Result<Vec<MigrationStatus>> {
+    let mut conn: PoolConnection<Postgres> = pool.acquire().await?;
+    let applied: HashMap<i64, AppliedMigration> = load_applied(&mut conn).await?;
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|m: &Migration| {
+            let record: Option<&AppliedMigration> = applied.get(&m.version);
+            let state: MigrationState = match record {
+                None => MigrationState::Pending,
+                Some(a) if a.checksum != m.checksum() => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: m.version,
+                name: m.name.to_string(),
+                state,
+                applied_at: record.and_then(|a: &AppliedMigration| a.applied_at),
+            }
+        })
+        .collect();
+
+    statuses.extend(unknown_applied(&applied).into_iter().map(|a: &AppliedMigration| MigrationStatus {
+        version: a.version,
+        name: a.name.clone(),
+        state: MigrationState::Unknown,
+        applied_at: a.applied_at,
+    }));
+    statuses.sort_by_key(|s: &MigrationStatus| s.version);
+
+    Ok(statuses)
+}
+
+// =============================================================================
+// INTERNALS
+// =============================================================================
+
+/// Takes the session-level migration lock, waiting while another instance migrates
+async fn acquire_lock(pool: &PgPool) -> Result<PoolConnection<Postgres>> {
+    let mut conn: PoolConnection<Postgres> = pool.acquire().await.context("Failed to acquire migration connection")?;
+
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to acquire migration lock")?;
+
+    Ok(conn)
+}
+
+async fn release_lock(mut conn: PoolConnection<Postgres>) {
+    if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+    {
+        // Closing the connection releases the lock as well
+        warn!("Failed to release migration lock: {}", e);
+        conn.detach();
+    }
+}
+
+async fn up_locked(conn: &mut PgConnection, target: Option<i64>, dry_run: bool) -> Result<MigrationReport> {
+    let applied: HashMap<i64, AppliedMigration> = load_applied(conn).await?;
+    verify_applied(&applied)?;
+
+    let pending: Vec<&Migration> = pending_migrations(MIGRATIONS, &applied, target);
+    let mut report: MigrationReport = MigrationReport {
+        pending: pending.iter().map(|m: &&Migration| m.version).collect(),
+        ..MigrationReport::default()
+    };
+
+    if pending.is_empty() {
+        info!("Database schema is up to date ({} migrations applied)", applied.len());
+        return Ok(report);
+    }
+
+    if dry_run {
+        for migration in &pending {
+            info!("[dry run] Would apply migration {}", migration.name);
+        }
+        return Ok(report);
+    }
+
+    ensure_migrations_table(conn).await?;
+
+    for migration in pending {
+        apply(conn, migration).await?;
+        report.applied.push(migration.version);
+    }
+
+    Ok(report)
+}
+
+async fn down_locked(conn: &mut PgConnection, target: Option<i64>, dry_run: bool) -> Result<MigrationReport> {
+    let applied: HashMap<i64, AppliedMigration> = load_applied(conn).await?;
+    verify_applied(&applied)?;
+
+    let mut report: MigrationReport = MigrationReport::default();
+    for version in revert_order(&applied, target) {
+        let migration: &Migration = MIGRATIONS
+            .iter()
+            .find(|m: &&Migration| m.version == version)
+            .with_context(|| format!("Cannot revert migration {}: this build does not know it", applied[&version].name))?;
+
+        if dry_run {
+            info!("[dry run] Would revert migration {}", migration.name);
+        } else {
+            revert(conn, migration).await?;
+        }
+        report.reverted.push(version);
+    }
+
+    if report.reverted.is_empty() {
+        info!("Nothing to revert");
+    }
+
+    Ok(report)
+}
+
+/// Runs one migration and records it, atomically
+async fn apply(conn: &mut PgConnection, migration: &Migration) -> Result<()> {
+    info!("Applying migration {}...", migration.name);
+    let started: Instant = Instant::now();
+
+    let mut tx: sqlx::Transaction<'_, Postgres> = sqlx::Connection::begin(&mut *conn).await?;
+
+    sqlx::raw_sql(migration.sql)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Migration {} failed", migration.name))?;
+
+    let execution_ms: i64 = started.elapsed().as_millis() as i64;
+    sqlx::query("INSERT INTO schema_migrations (version, name, checksum, execution_ms) VALUES ($1, $2, $3, $4)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(migration.checksum())
+        .bind(execution_ms)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to record migration {}", migration.name))?;
+
+    tx.commit().await.with_context(|| format!("Failed to commit migration {}", migration.name))?;
+
+    info!("Applied migration {} in {} ms", migration.name, execution_ms);
+    Ok(())
+}
+
+/// Runs one down-migration and removes its record, atomically
+async fn revert(conn: &mut PgConnection, migration: &Migration) -> Result<()> {
+    let down: &str = migration.down
+        .with_context(|| format!("Migration {} is irreversible (no {}.down.sql)", migration.name, migration.name))?;
+
+    info!("Reverting migration {}...", migration.name);
+    let mut tx: sqlx::Transaction<'_, Postgres> = sqlx::Connection::begin(&mut *conn).await?;
+
+    sqlx::raw_sql(down)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Reverting migration {} failed", migration.name))?;
+
+    sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+        .bind(migration.version)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await.with_context(|| format!("Failed to commit revert of {}", migration.name))?;
+
+    info!("Reverted migration {}", migration.name);
+    Ok(())
+}
+
+/// Fails on applied migrations whose SQL changed since; warns on versions this build does not know
+fn verify_applied(applied: &HashMap<i64, AppliedMigration>) -> Result<()> {
+    let edited: Vec<&str> = MIGRATIONS
+        .iter()
+        .filter(|m: &&Migration| applied.get(&m.version).is_some_and(|a: &AppliedMigration| a.checksum != m.checksum()))
+        .map(|m: &Migration| m.name)
+        .collect();
+
+    if !edited.is_empty() {
+        bail!(
+            "Migrations modified after being applied (checksum mismatch): {}. Revert the edits and add a new migration instead",
+            edited.join(", ")
+        );
+    }
+
+    for migration in unknown_applied(applied) {
+        // Expected briefly during rolling deploys, when a newer build already migrated
+        warn!("Database has migration {} which this build does not know", migration.name);
+    }
+
+    Ok(())
+}
+
+/// Applied versions above `target`, newest first; without a target only the latest one
+fn revert_order(applied: &HashMap<i64, AppliedMigration>, target: Option<i64>) -> Vec<i64> {
+    let mut versions: Vec<i64> = applied.keys().copied().collect();
+    versions.sort_unstable_by(|a: &i64, b: &i64| b.cmp(a));
+
+    let target: i64 = match target {
+        Some(t) => t,
+        None => versions.get(1).copied().unwrap_or(0),
+    };
+    versions.retain(|v: &i64| *v > target);
+    versions
+}
+
+/// Migrations of `migrations` not in `applied`, in version order, up to `target` (all when None)
+fn pending_migrations<'a>(migrations: &'a [Migration], applied: &HashMap<i64, AppliedMigration>, target: Option<i64>) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|m: &&Migration| !applied.contains_key(&m.version))
+        .filter(|m: &&Migration| target.is_none_or(|t: i64| m.version <= t))
+        .collect()
+}
+
+fn unknown_applied(applied: &HashMap<i64, AppliedMigration>) -> Vec<&AppliedMigration> {
+    let mut unknown: Vec<&AppliedMigration> = applied
+        .values()
+        .filter(|a: &&AppliedMigration| !MIGRATIONS.iter().any(|m: &Migration| m.version == a.version))
+        .collect();
+    unknown.sort_by_key(|a: &&AppliedMigration| a.version);
+    unknown
+}
+
+/// Reads the migration history; an absent table means nothing was applied yet
+async fn load_applied(conn: &mut PgConnection) -> Result<HashMap<i64, AppliedMigration>> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('schema_migrations') IS NOT NULL")
+        .fetch_one(&mut *conn)
+        .await?;
+    if !exists {
+        return Ok(HashMap::new());
+    }
+
+    let rows: Vec<AppliedMigration> = sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, name, checksum, applied_at, execution_ms FROM schema_migrations ORDER BY version"
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to read schema_migrations")?;
+
+    Ok(rows.into_iter().map(|m: AppliedMigration| (m.version, m)).collect())
+}
+
+async fn ensure_migrations_table(conn: &mut PgConnection) -> Result<()> {
+    sqlx::raw_sql(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            checksum VARCHAR(64) NOT NULL,
+            applied_at TIMESTAMPTZ DEFAULT NOW(),
+            execution_ms BIGINT NOT NULL
+        )
+        "#
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to create schema_migrations")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(migration: &Migration, checksum: String) -> (i64, AppliedMigration) {
+        let row: AppliedMigration = AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_string(),
+            checksum,
+            applied_at: None,
+            execution_ms: 0,
+        };
+        (migration.version, row)
+    }
+
+    fn applied_up_to(version: i64) -> HashMap<i64, AppliedMigration> {
+        MIGRATIONS
+            .iter()
+            .filter(|m: &&Migration| m.version <= version)
+            .map(|m: &Migration| applied(m, m.checksum()))
+            .collect()
+    }
+
+    fn versions(migrations: &[&Migration]) -> Vec<i64> {
+        migrations.iter().map(|m: &&Migration| m.version).collect()
+    }
+
+    #[test]
+    fn registry_is_in_strictly_increasing_version_order() {
+        assert!(!MIGRATIONS.is_empty());
+        assert_eq!(MIGRATIONS[0].version, 1);
+        assert!(MIGRATIONS.windows(2).all(|pair: &[Migration]| pair[0].version < pair[1].version));
+    }
+
+    #[test]
+    fn registry_names_carry_their_version_prefix() {
+        for migration in MIGRATIONS {
+            let prefix: i64 = migration.name.split('_').next().and_then(|v: &str| v.parse().ok()).expect("numeric prefix");
+            assert_eq!(prefix, migration.version, "{}", migration.name);
+            assert!(!migration.sql.trim().is_empty(), "{} is empty", migration.name);
+        }
+    }
+
+    #[test]
+    fn checksum_is_the_hex_sha256_of_the_sql() {
+        let migration: Migration = Migration { version: 1, name: "0001_test", sql: "", down: None };
+        assert_eq!(migration.checksum(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        let edited: Migration = Migration { sql: "SELECT 1;", ..migration };
+        assert_ne!(edited.checksum(), migration.checksum());
+        assert_eq!(edited.checksum().len(), 64);
+    }
+
+    #[test]
+    fn everything_is_pending_on_an_empty_database() {
+        let pending: Vec<&Migration> = pending_migrations(MIGRATIONS, &HashMap::new(), None);
+        assert_eq!(pending.len(), MIGRATIONS.len());
+        assert!(pending.windows(2).all(|pair: &[&Migration]| pair[0].version < pair[1].version));
+    }
+
+    #[test]
+    fn pending_skips_applied_versions_and_stops_at_the_target() {
+        let applied: HashMap<i64, AppliedMigration> = applied_up_to(2);
+        assert_eq!(versions(&pending_migrations(MIGRATIONS, &applied, Some(4))), [3, 4]);
+        assert_eq!(versions(&pending_migrations(MIGRATIONS, &applied, Some(2))), Vec::<i64>::new());
+        assert_eq!(pending_migrations(MIGRATIONS, &applied, None).len(), MIGRATIONS.len() - 2);
+    }
+
+    #[test]
+    fn pending_fills_gaps_left_by_out_of_order_applies() {
+        let migrations: [Migration; 3] = [
+            Migration { version: 1, name: "0001_a", sql: "SELECT 1;", down: None },
+            Migration { version: 2, name: "0002_b", sql: "SELECT 2;", down: None },
+            Migration { version: 3, name: "0003_c", sql: "SELECT 3;", down: None },
+        ];
+        let applied: HashMap<i64, AppliedMigration> = [applied(&migrations[0], migrations[0].checksum()), applied(&migrations[2], migrations[2].checksum())]
+            .into_iter()
+            .collect();
+        assert_eq!(versions(&pending_migrations(&migrations, &applied, None)), [2]);
+    }
+
+    #[test]
+    fn down_without_a_target_reverts_only_the_latest_migration() {
+        assert_eq!(revert_order(&applied_up_to(3), None), [3]);
+        assert_eq!(revert_order(&applied_up_to(1), None), [1]);
+        assert_eq!(revert_order(&HashMap::new(), None), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn down_to_a_target_reverts_newest_first() {
+        assert_eq!(revert_order(&applied_up_to(4), Some(1)), [4, 3, 2]);
+        assert_eq!(revert_order(&applied_up_to(4), Some(0)), [4, 3, 2, 1]);
+        assert_eq!(revert_order(&applied_up_to(2), Some(5)), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn verify_accepts_untouched_migrations() {
+        assert!(verify_applied(&applied_up_to(i64::MAX)).is_ok());
+        assert!(verify_applied(&HashMap::new()).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_edited_migrations() {
+        let mut history: HashMap<i64, AppliedMigration> = applied_up_to(2);
+        history.get_mut(&2).unwrap().checksum = "0".repeat(64);
+
+        let error: String = verify_applied(&history).unwrap_err().to_string();
+        assert!(error.contains(MIGRATIONS[1].name), "{}", error);
+        assert!(!error.contains(MIGRATIONS[0].name), "{}", error);
+    }
+
+    #[test]
+    fn unknown_versions_are_tolerated_and_sorted() {
+        let future: Migration = Migration { version: 9_998, name: "9998_future", sql: "SELECT 1;", down: None };
+        let later: Migration = Migration { version: 9_999, name: "9999_later", ..future };
+        let mut history: HashMap<i64, AppliedMigration> = applied_up_to(1);
+        history.extend([applied(&later, later.checksum()), applied(&future, future.checksum())]);
+
+        assert!(verify_applied(&history).is_ok());
+        let unknown: Vec<i64> = unknown_applied(&history).iter().map(|a: &&AppliedMigration| a.version).collect();
+        assert_eq!(unknown, [9_998, 9_999]);
+    }
+}
+
+// End of file: /src/database/migrations.rs
@@ -1,5 +1,19 @@
+pub mod cache;
+pub mod cache_store;
+pub mod circuit_breaker;
+pub mod migrations;
 pub mod postgres_service;
 pub mod redis_manager;
+pub mod replicas;
+pub mod tenant_pool;
+pub mod tenant_tables;
+pub mod tx_options;
 
-pub use postgres_service::DatabaseService;
-pub use redis_manager::RedisService;
+pub use cache::{CacheService, CachedDomain, Session};
+pub use cache_store::{CacheStore, CacheSubscription, ConnectionStats, MemoryStore};
+pub use circuit_breaker::{is_circuit_open, CircuitBreaker, CircuitOpen, CircuitState};
+pub use postgres_service::{is_connection_error, DatabaseService};
+pub use redis_manager::{is_redis_connection_error, RedisService};
+pub use replicas::{Replica, ReplicaSet};
+pub use tenant_pool::{is_missing_tenant_context, TenantScopedPool, TenantTx, MISSING_TENANT_CONTEXT};
+pub use tx_options::{is_retryable, IsolationLevel, RetryPolicy, TxOptions, TxTimeouts};
@@ -3,20 +3,19 @@
 // =============================================================================
 
 use std::sync::Arc;
+use std::time::Duration;
 use anyhow::{Context, Result};
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
-use sqlx::{ConnectOptions, PgPool, Executor};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::{ConnectOptions, PgPool};
 use tokio::sync::OnceCell;
-use tracing::{debug, info, log::LevelFilter};
+use tracing::{debug, info, warn, log::LevelFilter};
 
-use crate::config::environment::EnvironmentVariables;
-
-// =============================================================================
-// SQL CONSTANTS
-// =============================================================================
-
-/// Single initialization SQL script
-const INIT_SCHEMA_SQL: &str = include_str!("sql/schemas/schema_init.sql");
+use crate::config::environment::{EnvironmentVariables, ReplicaHost};
+use crate::database::replicas::{Replica, ReplicaSet};
+use crate::database::tenant_pool::{TenantScopedPool, TenantTx};
+use crate::database::tenant_tables::{TenantTable, TENANT_TABLES};
+use crate::database::tx_options::{RetryPolicy, TxOptions, TxTimeouts};
+use crate::database::migrations::{migrate_up, migration_status, MigrationReport, MigrationState, MigrationStatus};
 
 // =============================================================================
 // DATABASE SERVICE
@@ -28,6 +27,8 @@ const INIT_SCHEMA_SQL: &str = include_str!("sql/schemas/schema_init.sql");
 pub struct DatabaseService {
     /// Single connection pool for the application
     pool: Arc<OnceCell<PgPool>>,
+    /// Read replicas (`DB_REPLICA_HOSTS`), set up by `initialize()`
+    replicas: Arc<OnceCell<Arc<ReplicaSet>>>,
     /// Environment configuration
     config: Arc<EnvironmentVariables>,
 }
@@ -38,32 +39,90 @@ impl DatabaseService {
     pub fn new(config: Arc<EnvironmentVariables>) -> Self {
         Self {
             pool: Arc::new(OnceCell::new()),
+            replicas: Arc::new(OnceCell::new()),
+            config,
+        }
+    }
+
+    /// Creates a DatabaseService on top of an existing runtime pool, e.g. one set up by a
+    /// test or a lazy pool (`connect_lazy_with`) to simulate an unreachable server.
+    /// `connect()` keeps the given pool; `initialize()` still migrates and self-checks.
+    pub fn with_pool(config: Arc<EnvironmentVariables>, pool: PgPool) -> Self {
+        Self {
+            pool: Arc::new(OnceCell::new_with(Some(pool))),
+            replicas: Arc::new(OnceCell::new()),
             config,
         }
     }
 
-    /// Initializes the database service by creating the pool and running migrations.
+    /// Initializes the database service: runs migrations as the schema owner, creates
+    /// the runtime pool and refuses to continue if the runtime role bypasses RLS.
+    /// With `DB_MIGRATE_ON_STARTUP=false` migrations are left to `migrate up` and
+    /// pending ones are only reported.
     pub async fn initialize(&self) -> Result<()> {
         info!("Initializing DatabaseService (Single Schema)...");
 
-        // Initialize the pool if not already initialized
-        self.pool.get_or_try_init(|| async {
-            self.create_pool().await
-        }).await?;
+        if self.config.db_migrate_on_startup {
+            // Bring the schema up to date with a short-lived owner pool
+            let migration_pool: PgPool = self.migration_pool().await?;
+            let report: Result<MigrationReport> = migrate_up(&migration_pool, None, self.config.db_migrations_dry_run).await;
+            migration_pool.close().await;
+
+            let report: MigrationReport = report?;
+            if self.config.db_migrations_dry_run && !report.pending.is_empty() {
+                warn!("{} pending migrations were not applied (DB_MIGRATIONS_DRY_RUN)", report.pending.len());
+            }
+        }
 
-        // Get reference to the pool
+        self.connect().await?;
         let pool = self.get_pool()?;
 
-        // Run schema initialization
-        self.initialize_schema(pool).await?;
+        if !self.config.db_migrate_on_startup {
+            let pending: usize = migration_status(pool).await?
+                .iter()
+                .filter(|m: &&MigrationStatus| m.state == MigrationState::Pending)
+                .count();
+            if pending > 0 {
+                warn!("{} pending migrations; run `migrate up` (DB_MIGRATE_ON_STARTUP=false)", pending);
+            }
+        }
+
+        self.verify_rls_enforced().await?;
+        self.connect_replicas().await?;
 
         info!("DatabaseService initialized successfully");
         Ok(())
     }
 
+    /// Creates the runtime connection pool without touching the schema.
+    pub async fn connect(&self) -> Result<()> {
+        self.pool.get_or_try_init(|| async {
+            self.create_pool().await
+        }).await?;
+        Ok(())
+    }
+
+    /// Opens a small pool as the schema owner (`DB_MIGRATION_USER`, falling back to
+    /// the runtime credentials). The caller closes it once migrations are done.
+    pub async fn migration_pool(&self) -> Result<PgPool> {
+        let credentials: Option<(&str, &str)> = self.config.db_migration_user.as_deref()
+            .map(|user: &str| (user, self.config.db_migration_password.as_deref().unwrap_or_default()));
+        let options: PgConnectOptions = self.create_connect_options(credentials).await?;
+        let user: String = options.get_username().to_string();
+
+        PgPoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to connect as migration user {}", user))
+    }
+
     /// Gracefully shuts down the service.
     pub async fn shutdown(&self) {
         info!("Initiating DatabaseService shutdown...");
+        if let Some(replicas) = self.replicas.get() {
+            replicas.close().await;
+        }
         if let Some(pool) = self.pool.get() {
             pool.close().await;
             info!("Database connection pool closed");
@@ -72,11 +131,17 @@ impl DatabaseService {
         }
     }
 
-    /// Returns the connection pool.
+    /// Returns the connection pool, for global tables only.
+    /// Tenant tables go through `tenant_pool()` / `with_tenant()`.
     /// Errors if the pool has not been initialized.
     pub fn get_pool(&self) -> Result<&PgPool> {
         self.pool.get().ok_or_else(|| anyhow::anyhow!("Database pool not initialized"))
     }
+
+    /// Returns the read replicas, if any are configured.
+    pub fn replicas(&self) -> Option<&Arc<ReplicaSet>> {
+        self.replicas.get()
+    }
 }
 
 // =============================================================================
@@ -84,6 +149,34 @@ impl DatabaseService {
 // =============================================================================
 
 impl DatabaseService {
+    /// Returns the handle through which tenant tables are queried.
+    pub fn tenant_pool(&self) -> Result<TenantScopedPool> {
+        Ok(self.scoped(self.get_pool()?.clone()))
+    }
+
+    /// Wraps `pool` (the primary or a replica) with the configured retry policy and timeouts
+    fn scoped(&self, pool: PgPool) -> TenantScopedPool {
+        let retry: RetryPolicy = RetryPolicy {
+            max_retries: self.config.db_tx_max_retries,
+            base_delay: Duration::from_millis(self.config.db_tx_retry_base_delay_ms),
+        };
+        let timeouts: TxTimeouts = TxTimeouts::new()
+            .statement(Duration::from_millis(self.config.db_statement_timeout_ms))
+            .lock(Duration::from_millis(self.config.db_lock_timeout_ms))
+            .idle_in_transaction(Duration::from_millis(self.config.db_idle_in_transaction_timeout_ms));
+        TenantScopedPool::new(pool, retry, timeouts)
+    }
+
+    /// Begins a transaction bound to a tenant; commit it explicitly, dropping it rolls back.
+    pub async fn begin_tenant(&self, tenant_id: uuid::Uuid) -> Result<TenantTx> {
+        self.tenant_pool()?.begin(tenant_id).await
+    }
+
+    /// Like `begin_tenant`, with an explicit isolation level, access mode and timeouts.
+    pub async fn begin_tenant_with(&self, tenant_id: uuid::Uuid, options: TxOptions) -> Result<TenantTx> {
+        self.tenant_pool()?.begin_with(tenant_id, options).await
+    }
+
     /// Executes a closure within a tenant-scoped transaction.
     /// This ensures that `SET LOCAL app.current_tenant_id` is called before any logic.
     /// The transaction is automatically committed if the closure returns Ok.
@@ -91,30 +184,158 @@ impl DatabaseService {
     where
         F: for<'c> FnOnce(&'c mut sqlx::Transaction<'_, sqlx::Postgres>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T>> + Send + 'c>> + Send,
     {
+        self.tenant_pool()?.with_tenant(tenant_id, block).await
+    }
+
+    /// Like `with_tenant`, with an isolation level and retries on serialization
+    /// failures and deadlocks. The closure must be safe to run more than once.
+    pub async fn with_tenant_retry<F, T>(&self, tenant_id: uuid::Uuid, options: TxOptions, block: F) -> Result<T>
+    where
+        F: for<'c> Fn(&'c mut sqlx::Transaction<'_, sqlx::Postgres>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T>> + Send + 'c>> + Send + Sync,
+    {
+        self.tenant_pool()?.with_tenant_retry(tenant_id, options, block).await
+    }
+
+    /// Runs a read-only tenant transaction on a read replica when one is healthy and
+    /// within `DB_REPLICA_MAX_LAG_MS`, on the primary otherwise.
+    ///
+    /// `session` identifies the caller for read-your-writes: after `record_write(session)`
+    /// its reads stay on the primary for `DB_READ_YOUR_WRITES_MS`. A replica that cannot be
+    /// reached is ejected and the read re-runs on the primary, so the closure must be safe
+    /// to run more than once (as for `with_tenant_retry`).
+    pub async fn with_tenant_read<F, T>(&self, tenant_id: uuid::Uuid, session: Option<&str>, block: F) -> Result<T>
+    where
+        F: for<'c> Fn(&'c mut sqlx::Transaction<'_, sqlx::Postgres>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T>> + Send + 'c>> + Send + Sync,
+    {
+        let options: TxOptions = TxOptions::new().read_only();
+
+        if let Some(replicas) = self.replicas.get() {
+            let sticky: bool = session.is_some_and(|key: &str| replicas.is_sticky(key));
+            let replica: Option<&Replica> = if sticky { None } else { replicas.pick() };
+
+            if let Some(replica) = replica {
+                match self.scoped(replica.pool.clone()).with_tenant_retry(tenant_id, options, &block).await {
+                    Err(e) if is_connection_error(&e) => replicas.eject(replica, &e),
+                    result => return result,
+                }
+            }
+        }
+
+        self.tenant_pool()?.with_tenant_retry(tenant_id, options, block).await
+    }
+
+    /// Pins `session` to the primary for `DB_READ_YOUR_WRITES_MS` after it wrote.
+    pub fn record_write(&self, session: &str) {
+        if let Some(replicas) = self.replicas.get() {
+            replicas.record_write(session);
+        }
+    }
+}
+
+// =============================================================================
+// CUSTOM DOMAIN RESOLUTION
+// =============================================================================
+
+impl DatabaseService {
+    /// Resolves a verified custom hostname to its tenant.
+    /// Runs before any tenant context exists, so it opts into the
+    /// `domain_resolution_policy` which only exposes verified domains.
+    pub async fn resolve_tenant_domain(&self, hostname: &str) -> Result<Option<uuid::Uuid>> {
         let pool = self.get_pool()?;
         let mut tx = pool.begin().await.context("Failed to begin transaction")?;
 
-        // Inject Tenant Context
-        sqlx::query("SET LOCAL app.current_tenant_id = $1")
-            .bind(tenant_id)
+        sqlx::query("SELECT set_config('app.domain_resolution', 'on', true)")
             .execute(&mut *tx)
             .await
-            .context("Failed to set tenant context")?;
+            .context("Failed to enable domain resolution")?;
 
-        // Execute Business Logic
-        let result = block(&mut tx).await;
+        let tenant_id: Option<uuid::Uuid> = sqlx::query_scalar(
+            "SELECT tenant_id FROM tenant_domains WHERE hostname = $1 AND status = 'verified'"
+        )
+        .bind(hostname)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to resolve tenant domain")?;
 
-        match result {
-            Ok(val) => {
-                tx.commit().await.context("Failed to commit transaction")?;
-                Ok(val)
-            }
-            Err(e) => {
-                // Rollback is automatic on drop, but explicit rollback is good for logging
-                let _ = tx.rollback().await;
-                Err(e)
+        tx.commit().await.context("Failed to commit transaction")?;
+        Ok(tenant_id)
+    }
+}
+
+// =============================================================================
+// RLS SELF-CHECK
+// =============================================================================
+
+/// RLS flags of a tenant table; all None when the table is missing
+#[derive(sqlx::FromRow)]
+struct TableRlsState {
+    name: String,
+    enabled: Option<bool>,
+    forced: Option<bool>,
+    owned: Option<bool>,
+}
+
+impl DatabaseService {
+    /// Fails startup if the runtime role could read or write across tenants:
+    /// superusers and BYPASSRLS roles skip every policy, and table owners can turn
+    /// RLS off. Every tenant table must also have RLS enabled and forced.
+    /// `DB_ALLOW_RLS_BYPASS=true` downgrades the failure to a warning.
+    async fn verify_rls_enforced(&self) -> Result<()> {
+        let pool = self.get_pool()?;
+        let mut problems: Vec<String> = Vec::new();
+
+        let (role, is_superuser, bypasses_rls): (String, bool, bool) = sqlx::query_as(
+            "SELECT rolname::text, rolsuper, rolbypassrls FROM pg_roles WHERE rolname = current_user"
+        )
+        .fetch_one(pool)
+        .await
+        .context("Failed to read runtime role attributes")?;
+
+        if is_superuser {
+            problems.push(format!("role {} is a superuser", role));
+        }
+        if bypasses_rls {
+            problems.push(format!("role {} has BYPASSRLS", role));
+        }
+
+        let table_names: Vec<&str> = TENANT_TABLES.iter().map(|t: &TenantTable| t.name).collect();
+        let tables: Vec<TableRlsState> = sqlx::query_as(
+            r#"
+            SELECT t.name, c.relrowsecurity AS enabled, c.relforcerowsecurity AS forced, pg_has_role(c.relowner, 'USAGE') AS owned
+            FROM unnest($1::text[]) AS t(name)
+            LEFT JOIN pg_class c ON c.oid = to_regclass(t.name)
+            "#
+        )
+        .bind(&table_names)
+        .fetch_all(pool)
+        .await
+        .context("Failed to read row level security settings")?;
+
+        for TableRlsState { name: table, enabled, forced, owned } in tables {
+            match (enabled, forced, owned) {
+                (None, _, _) => problems.push(format!("table {} does not exist", table)),
+                (Some(false), _, _) => problems.push(format!("RLS is disabled on {}", table)),
+                (_, Some(false), _) => problems.push(format!("RLS is not forced on {}", table)),
+                (_, _, Some(true)) if !is_superuser => problems.push(format!("role {} owns {}", role, table)),
+                _ => {}
             }
         }
+
+        if problems.is_empty() {
+            info!("Row level security enforced for runtime role {}", role);
+            return Ok(());
+        }
+
+        let summary: String = problems.join("; ");
+        if self.config.db_allow_rls_bypass {
+            warn!("Tenant isolation is NOT enforced ({}); continuing because DB_ALLOW_RLS_BYPASS=true", summary);
+            return Ok(());
+        }
+
+        anyhow::bail!(
+            "Refusing to start: tenant isolation is not enforced ({}). Connect as a member of app_runtime (see migration 0008)",
+            summary
+        )
     }
 }
 
@@ -125,12 +346,14 @@ impl DatabaseService {
 impl DatabaseService {
     /// Creates the connection pool based on environment config
     async fn create_pool(&self) -> Result<PgPool> {
-        let connect_options = self.create_connect_options().await?;
+        let mut connect_options = self.create_connect_options(None).await?;
+
+        // Tenant tables queried without a tenant context raise instead of matching nothing
+        if self.config.db_strict_tenant_context {
+            connect_options = connect_options.options([("app.strict_tenant_context", "on")]);
+        }
 
-        let pool = PgPoolOptions::new()
-            .max_connections(20) // Adjust based on load/Cloud Run specs
-            .min_connections(5)
-            .idle_timeout(std::time::Duration::from_secs(30))
+        let pool = self.pool_options()
             .connect_with(connect_options)
             .await
             .context("Failed to create database connection pool")?;
@@ -138,15 +361,89 @@ impl DatabaseService {
         Ok(pool)
     }
 
-    /// Creates connection options with SSL and UTC timezone
-    async fn create_connect_options(&self) -> Result<PgConnectOptions> {
-        let mut options = PgConnectOptions::new()
-            .host(&self.config.db_host)
-            .port(self.config.db_port)
-            .username(&self.config.db_user)
-            .password(&self.config.db_password)
-            .database(&self.config.db_name)
-            .log_statements(LevelFilter::Debug);
+    /// Opens lazy pools for `DB_REPLICA_HOSTS`, probes them once and keeps probing in
+    /// the background. An unreachable replica does not stop startup; it stays out of rotation.
+    async fn connect_replicas(&self) -> Result<()> {
+        if self.config.db_replica_hosts.is_empty() {
+            return Ok(());
+        }
+
+        let mut pools: Vec<(String, PgPool)> = Vec::new();
+        for replica in &self.config.db_replica_hosts {
+            let options: PgConnectOptions = self.replica_connect_options(replica).await?;
+            pools.push((replica.to_string(), self.pool_options().connect_lazy_with(options)));
+        }
+
+        let replicas: Arc<ReplicaSet> = Arc::new(ReplicaSet::new(
+            self.get_pool()?.clone(),
+            pools,
+            Duration::from_millis(self.config.db_replica_max_lag_ms),
+            Duration::from_millis(self.config.db_read_your_writes_ms),
+        ));
+        replicas.probe().await;
+        replicas.start_probing(Duration::from_secs(self.config.db_replica_check_interval_secs));
+
+        let healthy: usize = replicas.replicas().iter().filter(|r: &&Replica| r.is_healthy()).count();
+        info!("{} of {} read replicas healthy", healthy, replicas.replicas().len());
+
+        if self.replicas.set(replicas).is_err() {
+            warn!("Read replicas were already initialized");
+        }
+        Ok(())
+    }
+
+    /// The runtime connection options, pointed at a replica
+    async fn replica_connect_options(&self, replica: &ReplicaHost) -> Result<PgConnectOptions> {
+        let mut options: PgConnectOptions = self.create_connect_options(None).await?;
+        if self.config.db_strict_tenant_context {
+            options = options.options([("app.strict_tenant_context", "on")]);
+        }
+
+        if replica.host.starts_with('/') {
+            return Ok(options.socket(&replica.host).ssl_mode(PgSslMode::Disable));
+        }
+        // PgConnectOptions cannot drop a socket once set, and it would win over the host
+        if options.get_socket().is_some() {
+            anyhow::bail!("Read replica {} must be a socket directory when the primary uses a Unix socket", replica);
+        }
+        let port: u16 = replica.port.unwrap_or(options.get_port());
+        Ok(options.host(&replica.host).port(port))
+    }
+
+    /// Pool settings shared by the primary and replica pools
+    fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.config.db_pool_max_connections)
+            .min_connections(self.config.db_pool_min_connections)
+            .acquire_timeout(Duration::from_secs(self.config.db_pool_acquire_timeout_secs))
+            .idle_timeout(non_zero_secs(self.config.db_pool_idle_timeout_secs))
+            .max_lifetime(non_zero_secs(self.config.db_pool_max_lifetime_secs))
+            .test_before_acquire(self.config.db_pool_test_before_acquire)
+    }
+
+    /// Creates connection options from `DATABASE_URL` or the `DB_*` variables, with SSL
+    /// and UTC timezone. `credentials` replaces the configured user and password.
+    async fn create_connect_options(&self, credentials: Option<(&str, &str)>) -> Result<PgConnectOptions> {
+        let mut options: PgConnectOptions = match &self.config.database_url {
+            Some(url) => url.parse::<PgConnectOptions>().context("Invalid DATABASE_URL")?,
+            None => {
+                let options: PgConnectOptions = PgConnectOptions::new()
+                    .port(self.config.db_port)
+                    .username(&self.config.db_user)
+                    .password(&self.config.db_password)
+                    .database(&self.config.db_name);
+                // A path is a Unix-socket directory (e.g. /cloudsql/project:region:instance)
+                if self.config.db_host.starts_with('/') {
+                    options.socket(self.config.db_host.as_ref())
+                } else {
+                    options.host(&self.config.db_host)
+                }
+            }
+        };
+        if let Some((user, password)) = credentials {
+            options = options.username(user).password(password);
+        }
+        options = options.log_statements(LevelFilter::Debug);
 
         // Always use UTC and standard app name
         options = options.options([
@@ -154,26 +451,107 @@ impl DatabaseService {
             ("application_name", "rust-axum-api-rls")
         ]);
 
-        // Configure SSL based on environment
+        // TLS mode: DB_SSL_MODE, else sslmode from DATABASE_URL, else based on environment
+        let explicit: Option<(PgSslMode, &str)> = match (self.config.db_ssl_mode, &self.config.database_url) {
+            (Some(mode), _) => Some((mode, "DB_SSL_MODE")),
+            (None, Some(url)) => url_ssl_mode(url)?.map(|mode: PgSslMode| (mode, "sslmode in DATABASE_URL")),
+            (None, None) => None,
+        };
         let is_development = self.config.environment == "development";
-        if !is_development {
-            options = options.ssl_mode(sqlx::postgres::PgSslMode::Require);
-        } else {
-            options = options.ssl_mode(sqlx::postgres::PgSslMode::Prefer);
+        let mode: PgSslMode = ssl_mode_for(options.get_socket().is_some(), explicit, is_development)?;
+        options = options.ssl_mode(mode);
+
+        // Certificates for verify-ca / verify-full and mutual TLS
+        if let Some(path) = &self.config.db_ssl_root_cert {
+            options = options.ssl_root_cert(path.as_ref());
+        }
+        if let (Some(cert), Some(key)) = (&self.config.db_ssl_client_cert, &self.config.db_ssl_client_key) {
+            options = options.ssl_client_cert(cert.as_ref()).ssl_client_key(key.as_ref());
         }
 
         Ok(options)
     }
+}
 
-    /// Runs the initialization SQL
-    async fn initialize_schema(&self, pool: &PgPool) -> Result<()> {
-        info!("Executing schema initialization...");
-        
-        pool.execute(INIT_SCHEMA_SQL)
-            .await
-            .context("Failed to execute schema initialization SQL")?;
-            
-        info!("Schema initialization completed");
-        Ok(())
+/// `sslmode` (or `ssl-mode`) query parameter of a `DATABASE_URL`
+fn url_ssl_mode(url: &str) -> Result<Option<PgSslMode>> {
+    let url: reqwest::Url = reqwest::Url::parse(url).context("Invalid DATABASE_URL")?;
+    url.query_pairs()
+        .find(|(key, _)| key == "sslmode" || key == "ssl-mode")
+        .map(|(_, value)| value.parse::<PgSslMode>().context("Invalid sslmode in DATABASE_URL"))
+        .transpose()
+}
+
+/// TLS mode for a connection: the explicitly configured mode (and where it came from),
+/// else `require` outside development and `prefer` in development.
+/// Unix sockets do not support TLS, so a socket with a mode requiring it is a config error.
+fn ssl_mode_for(socket: bool, explicit: Option<(PgSslMode, &str)>, is_development: bool) -> Result<PgSslMode> {
+    match explicit {
+        Some((PgSslMode::Require | PgSslMode::VerifyCa | PgSslMode::VerifyFull, source)) if socket => {
+            anyhow::bail!("{} requires TLS, which Unix-socket connections do not support (use disable, allow or prefer)", source)
+        }
+        _ if socket => Ok(PgSslMode::Disable),
+        Some((mode, source)) => {
+            debug!("Using the TLS mode from {}", source);
+            Ok(mode)
+        }
+        None if is_development => Ok(PgSslMode::Prefer),
+        None => Ok(PgSslMode::Require),
+    }
+}
+
+/// Pool durations where 0 means "never"
+fn non_zero_secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// SQLSTATE sent while the server is starting up, shutting down or in recovery
+const CANNOT_CONNECT_NOW: &str = "57P03";
+
+/// True if the error means the server could not be reached (or is not accepting
+/// connections yet), as opposed to a query or authentication error
+pub fn is_connection_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|e| e.downcast_ref::<sqlx::Error>())
+        .any(|e: &sqlx::Error| match e {
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Protocol(_) => true,
+            sqlx::Error::Database(db) => db.code().as_deref() == Some(CANNOT_CONNECT_NOW),
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sslmode_is_read_from_the_query_string_only() {
+        let mode = |url: &str| url_ssl_mode(url).unwrap();
+        assert!(matches!(mode("postgres://u:p@db/app?sslmode=verify-full"), Some(PgSslMode::VerifyFull)));
+        assert!(matches!(mode("postgres://u:p@db/app?application_name=x&ssl-mode=disable"), Some(PgSslMode::Disable)));
+        assert!(mode("postgres://u:sslmode=require@db/app").is_none());
+        assert!(mode("postgres://u:p@db/sslmode=require").is_none());
+        assert!(mode("postgres://u:p@db/app?xsslmode=require").is_none());
+        assert!(url_ssl_mode("postgres://u:p@db/app?sslmode=sometimes").is_err());
+    }
+
+    #[test]
+    fn sockets_reject_modes_that_require_tls() {
+        for mode in [PgSslMode::Require, PgSslMode::VerifyCa, PgSslMode::VerifyFull] {
+            let error: anyhow::Error = ssl_mode_for(true, Some((mode, "DB_SSL_MODE")), false).unwrap_err();
+            assert!(error.to_string().starts_with("DB_SSL_MODE requires TLS"), "{}", error);
+        }
+        for mode in [PgSslMode::Disable, PgSslMode::Allow, PgSslMode::Prefer] {
+            assert!(matches!(ssl_mode_for(true, Some((mode, "DB_SSL_MODE")), false), Ok(PgSslMode::Disable)));
+        }
+        assert!(matches!(ssl_mode_for(true, None, false), Ok(PgSslMode::Disable)));
+    }
+
+    #[test]
+    fn tcp_uses_the_explicit_mode_or_the_environment_default() {
+        assert!(matches!(ssl_mode_for(false, Some((PgSslMode::Allow, "DB_SSL_MODE")), false), Ok(PgSslMode::Allow)));
+        assert!(matches!(ssl_mode_for(false, None, false), Ok(PgSslMode::Require)));
+        assert!(matches!(ssl_mode_for(false, None, true), Ok(PgSslMode::Prefer)));
     }
 }
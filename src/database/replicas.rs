@@ -0,0 +1,312 @@
+// Read replicas: round-robin selection, health/lag probing and read-your-writes stickiness
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sqlx::PgPool;
+use tokio::task::AbortHandle;
+use tracing::{info, warn};
+
+/// Sticky sessions are pruned once the map grows past this size
+const STICKY_PRUNE_THRESHOLD: usize = 1024;
+
+/// One read replica and its last probed state
+#[derive(Debug)]
+pub struct Replica {
+    /// `host[:port]`, for logs
+    pub name: String,
+    pub pool: PgPool,
+    healthy: AtomicBool,
+    lag_ms: AtomicU64,
+}
+
+impl Replica {
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Replay lag measured by the last probe
+    pub fn lag(&self) -> Duration {
+        Duration::from_millis(self.lag_ms.load(Ordering::Relaxed))
+    }
+}
+
+/// The replicas behind `DatabaseService::with_tenant_read`.
+///
+/// A replica is used while its last probe succeeded and its lag is within `max_lag`;
+/// otherwise reads fall back to the primary. Sessions that wrote recently are pinned
+/// to the primary for `sticky_window` so they read their own writes.
+///
+/// Stickiness is tracked in memory, per process: with several API instances behind a
+/// load balancer, a write recorded by one instance does not pin reads served by another.
+#[derive(Debug)]
+pub struct ReplicaSet {
+    /// Probed for its current WAL position, which replicas are compared against
+    primary: PgPool,
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+    max_lag: Duration,
+    sticky_window: Duration,
+    /// Session key -> end of its primary-only window
+    recent_writes: Mutex<HashMap<String, Instant>>,
+    probe_task: Mutex<Option<AbortHandle>>,
+}
+
+impl ReplicaSet {
+    pub(crate) fn new(primary: PgPool, replicas: Vec<(String, PgPool)>, max_lag: Duration, sticky_window: Duration) -> Self {
+        let replicas: Vec<Replica> = replicas
+            .into_iter()
+            .map(|(name, pool)| Replica {
+                name,
+                pool,
+                // Unhealthy until the first probe succeeds
+                healthy: AtomicBool::new(false),
+                lag_ms: AtomicU64::new(0),
+            })
+            .collect();
+
+        Self {
+            primary,
+            replicas,
+            next: AtomicUsize::new(0),
+            max_lag,
+            sticky_window,
+            recent_writes: Mutex::new(HashMap::new()),
+            probe_task: Mutex::new(None),
+        }
+    }
+
+    pub fn replicas(&self) -> &[Replica] {
+        &self.replicas
+    }
+
+    /// Next usable replica in round-robin order, if any
+    pub fn pick(&self) -> Option<&Replica> {
+        let count: usize = self.replicas.len();
+        let start: usize = self.next.fetch_add(1, Ordering::Relaxed);
+
+        (0..count)
+            .map(|offset: usize| &self.replicas[(start + offset) % count])
+            .find(|replica: &&Replica| replica.is_healthy() && replica.lag() <= self.max_lag)
+    }
+
+    /// Pins `session` to the primary for the read-your-writes window
+    pub fn record_write(&self, session: &str) {
+        if self.sticky_window.is_zero() {
+            return;
+        }
+        let now: Instant = Instant::now();
+        let mut writes = self.recent_writes.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if writes.len() >= STICKY_PRUNE_THRESHOLD {
+            writes.retain(|_, until: &mut Instant| *until > now);
+        }
+        writes.insert(session.to_string(), now + self.sticky_window);
+    }
+
+    /// True while `session` is inside its read-your-writes window
+    pub fn is_sticky(&self, session: &str) -> bool {
+        let writes = self.recent_writes.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        writes.get(session).is_some_and(|until: &Instant| *until > Instant::now())
+    }
+
+    /// Takes a replica out of rotation until the next successful probe
+    pub(crate) fn eject(&self, replica: &Replica, error: &anyhow::Error) {
+        if replica.healthy.swap(false, Ordering::Relaxed) {
+            warn!("Read replica {} ejected: {}", replica.name, error);
+        }
+    }
+
+    /// Checks every replica once, updating health and lag
+    pub(crate) async fn probe(&self) {
+        // Replicas are compared with the primary rather than with their own receive
+        // position, so one whose WAL receiver disconnected does not look current
+        let primary_lsn: String = match sqlx::query_scalar("SELECT pg_current_wal_lsn()::text")
+            .fetch_one(&self.primary)
+            .await
+        {
+            Ok(lsn) => lsn,
+            Err(e) => {
+                warn!("Read replica probe skipped, the primary's WAL position is unavailable: {}", e);
+                return;
+            }
+        };
+
+        for replica in &self.replicas {
+            // Lag is 0 once the primary's position has been replayed, so an idle primary
+            // does not make its replicas look stale. NULL: behind, with nothing replayed yet.
+            let lag: Result<Option<i64>, sqlx::Error> = sqlx::query_scalar(
+                r#"
+                SELECT CASE
+                    WHEN NOT pg_is_in_recovery() THEN 0
+                    WHEN pg_last_wal_replay_lsn() >= $1::pg_lsn THEN 0
+                    ELSE (EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()) * 1000)::bigint
+                END
+                "#
+            )
+            .bind(&primary_lsn)
+            .fetch_one(&replica.pool)
+            .await;
+
+            match lag {
+                Ok(lag_ms) => {
+                    let lag_ms: u64 = replay_lag_ms(lag_ms);
+                    replica.lag_ms.store(lag_ms, Ordering::Relaxed);
+                    if !replica.healthy.swap(true, Ordering::Relaxed) {
+                        info!("Read replica {} is healthy (lag {} ms)", replica.name, lag_ms);
+                    }
+                    if lag_ms == u64::MAX {
+                        warn!("Read replica {} has not replayed up to the primary, reads use the primary", replica.name);
+                    } else if replica.lag() > self.max_lag {
+                        warn!("Read replica {} lags {} ms behind the primary, reads use the primary", replica.name, lag_ms);
+                    }
+                }
+                Err(e) => {
+                    if replica.healthy.swap(false, Ordering::Relaxed) {
+                        warn!("Read replica {} failed its health check: {}", replica.name, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Probes every `interval` in the background until `close()`
+    pub(crate) fn start_probing(self: &Arc<Self>, interval: Duration) {
+        let replicas: Arc<ReplicaSet> = Arc::clone(self);
+        let handle: AbortHandle = tokio::spawn(async move {
+            let mut ticker: tokio::time::Interval = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                replicas.probe().await;
+            }
+        })
+        .abort_handle();
+
+        *self.probe_task.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(handle);
+    }
+
+    /// Stops probing and closes the replica pools
+    pub(crate) async fn close(&self) {
+        if let Some(handle) = self.probe_task.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() {
+            handle.abort();
+        }
+        for replica in &self.replicas {
+            replica.pool.close().await;
+        }
+    }
+}
+
+/// Lag reported by the probe query in milliseconds: NULL (nothing replayed) is unbounded,
+/// and a negative value (replica clock ahead of the primary's) counts as caught up
+fn replay_lag_ms(lag_ms: Option<i64>) -> u64 {
+    lag_ms.map_or(u64::MAX, |ms: i64| ms.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    /// Pools that never connect; selection only looks at the probed state
+    fn replica_set(names: &[&str], max_lag: Duration, sticky_window: Duration) -> ReplicaSet {
+        let pool = || PgPoolOptions::new().connect_lazy("postgres://app@localhost/app").unwrap();
+        let replicas: Vec<(String, PgPool)> = names.iter().map(|name: &&str| (name.to_string(), pool())).collect();
+        ReplicaSet::new(pool(), replicas, max_lag, sticky_window)
+    }
+
+    fn set_probed(replica: &Replica, healthy: bool, lag_ms: u64) {
+        replica.healthy.store(healthy, Ordering::Relaxed);
+        replica.lag_ms.store(lag_ms, Ordering::Relaxed);
+    }
+
+    fn picked(set: &ReplicaSet, picks: usize) -> Vec<Option<String>> {
+        (0..picks).map(|_| set.pick().map(|replica: &Replica| replica.name.clone())).collect()
+    }
+
+    #[test]
+    fn replay_lag_handles_null_and_clock_skew() {
+        assert_eq!(replay_lag_ms(Some(1_500)), 1_500);
+        assert_eq!(replay_lag_ms(Some(0)), 0);
+        assert_eq!(replay_lag_ms(Some(-20)), 0);
+        assert_eq!(replay_lag_ms(None), u64::MAX);
+        assert!(Duration::from_millis(replay_lag_ms(None)) > Duration::from_secs(3_600));
+    }
+
+    #[tokio::test]
+    async fn replicas_are_unused_until_probed() {
+        let set: ReplicaSet = replica_set(&["a", "b"], Duration::from_secs(5), Duration::ZERO);
+        assert!(set.pick().is_none());
+    }
+
+    #[tokio::test]
+    async fn pick_rotates_over_usable_replicas() {
+        let set: ReplicaSet = replica_set(&["a", "b", "c"], Duration::from_secs(5), Duration::ZERO);
+        for replica in set.replicas() {
+            set_probed(replica, true, 0);
+        }
+        let names: Vec<Option<String>> = picked(&set, 6);
+        assert_eq!(names, ["a", "b", "c", "a", "b", "c"].map(|n: &str| Some(n.to_string())));
+    }
+
+    #[tokio::test]
+    async fn pick_skips_unhealthy_and_lagging_replicas() {
+        let set: ReplicaSet = replica_set(&["a", "b", "c"], Duration::from_millis(500), Duration::ZERO);
+        set_probed(&set.replicas()[0], false, 0);
+        set_probed(&set.replicas()[1], true, 501);
+        set_probed(&set.replicas()[2], true, 500);
+        assert!(picked(&set, 3).iter().all(|name: &Option<String>| name.as_deref() == Some("c")));
+
+        set_probed(&set.replicas()[2], true, replay_lag_ms(None));
+        assert!(set.pick().is_none());
+    }
+
+    #[tokio::test]
+    async fn ejected_replicas_leave_the_rotation() {
+        let set: ReplicaSet = replica_set(&["a", "b"], Duration::from_secs(5), Duration::ZERO);
+        set_probed(&set.replicas()[0], true, 0);
+        set_probed(&set.replicas()[1], true, 0);
+
+        set.eject(&set.replicas()[0], &anyhow::anyhow!("connection reset"));
+        assert!(picked(&set, 4).iter().all(|name: &Option<String>| name.as_deref() == Some("b")));
+    }
+
+    #[tokio::test]
+    async fn writes_pin_only_their_own_session() {
+        let set: ReplicaSet = replica_set(&[], Duration::from_secs(5), Duration::from_secs(60));
+        set.record_write("user-1");
+        assert!(set.is_sticky("user-1"));
+        assert!(!set.is_sticky("user-2"));
+    }
+
+    #[tokio::test]
+    async fn stickiness_expires_after_the_window() {
+        let set: ReplicaSet = replica_set(&[], Duration::from_secs(5), Duration::from_millis(20));
+        set.record_write("user-1");
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(!set.is_sticky("user-1"));
+    }
+
+    #[tokio::test]
+    async fn a_zero_window_disables_stickiness() {
+        let set: ReplicaSet = replica_set(&[], Duration::from_secs(5), Duration::ZERO);
+        set.record_write("user-1");
+        assert!(!set.is_sticky("user-1"));
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_pruned_once_the_map_is_full() {
+        let set: ReplicaSet = replica_set(&[], Duration::from_secs(5), Duration::from_millis(20));
+        for i in 0..STICKY_PRUNE_THRESHOLD {
+            set.record_write(&format!("user-{}", i));
+        }
+        std::thread::sleep(Duration::from_millis(40));
+        set.record_write("fresh");
+
+        let writes = set.recent_writes.lock().unwrap();
+        assert_eq!(writes.len(), 1);
+        assert!(writes.contains_key("fresh"));
+    }
+}
+
+// End of file: /src/database/replicas.rs
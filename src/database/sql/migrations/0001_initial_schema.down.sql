@@ -0,0 +1,5 @@
+-- Revert 0001: Helper functions, tenants and users
+
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS tenants;
+DROP FUNCTION IF EXISTS update_updated_at_column();
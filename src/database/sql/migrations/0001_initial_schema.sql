@@ -1,5 +1,5 @@
 -- =============================================================================
--- SINGLE SCHEMA INITIALIZATION
+-- 0001: Helper functions, tenants and users
 -- =============================================================================
 
 -- 1. Helper Functions
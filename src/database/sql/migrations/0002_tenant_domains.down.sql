@@ -0,0 +1,3 @@
+-- Revert 0002: Custom tenant domains
+
+DROP TABLE IF EXISTS tenant_domains;
@@ -0,0 +1,44 @@
+-- =============================================================================
+-- 0002: Custom tenant domains
+-- =============================================================================
+
+-- Tenant Domains Table (With RLS)
+-- Custom hostnames a tenant serves the API from, verified through a DNS TXT record.
+CREATE TABLE IF NOT EXISTS tenant_domains (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    hostname VARCHAR(253) NOT NULL,
+    verification_token VARCHAR NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'verified', 'failed')),
+    verified_at TIMESTAMPTZ,
+    last_checked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(tenant_id, hostname)
+);
+
+-- A hostname can be claimed by several tenants but verified by only one
+CREATE UNIQUE INDEX IF NOT EXISTS tenant_domains_verified_hostname_idx
+    ON tenant_domains (hostname)
+    WHERE status = 'verified';
+
+-- Enable RLS on tenant_domains
+ALTER TABLE tenant_domains ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_policy ON tenant_domains;
+CREATE POLICY tenant_isolation_policy ON tenant_domains
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+-- Host header resolution runs before any tenant is known, so verified
+-- domains are readable only inside a transaction that opts in explicitly
+DROP POLICY IF EXISTS domain_resolution_policy ON tenant_domains;
+CREATE POLICY domain_resolution_policy ON tenant_domains
+    FOR SELECT
+    USING (status = 'verified' AND current_setting('app.domain_resolution', true) = 'on');
+
+-- Trigger for tenant_domains updated_at
+DROP TRIGGER IF EXISTS update_tenant_domains_updated_at ON tenant_domains;
+CREATE TRIGGER update_tenant_domains_updated_at
+    BEFORE UPDATE ON tenant_domains
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
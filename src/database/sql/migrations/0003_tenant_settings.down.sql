@@ -0,0 +1,3 @@
+-- Revert 0003: Per-tenant settings
+
+DROP TABLE IF EXISTS tenant_settings;
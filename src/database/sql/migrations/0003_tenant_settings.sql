@@ -0,0 +1,28 @@
+-- =============================================================================
+-- 0003: Per-tenant settings
+-- =============================================================================
+
+-- Tenant Settings Table (With RLS)
+-- One JSONB document per tenant, validated against the Rust-side TenantSettings struct.
+-- `version` is bumped on every write for optimistic concurrency.
+CREATE TABLE IF NOT EXISTS tenant_settings (
+    tenant_id UUID PRIMARY KEY REFERENCES tenants(id),
+    settings JSONB NOT NULL DEFAULT '{}'::jsonb,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Enable RLS on tenant_settings
+ALTER TABLE tenant_settings ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_policy ON tenant_settings;
+CREATE POLICY tenant_isolation_policy ON tenant_settings
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+-- Trigger for tenant_settings updated_at
+DROP TRIGGER IF EXISTS update_tenant_settings_updated_at ON tenant_settings;
+CREATE TRIGGER update_tenant_settings_updated_at
+    BEFORE UPDATE ON tenant_settings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+-- Revert 0004: Feature flags and tenant overrides
+
+DROP TABLE IF EXISTS tenant_feature_flags;
+DROP TABLE IF EXISTS feature_flags;
@@ -0,0 +1,46 @@
+-- =============================================================================
+-- 0004: Feature flags and tenant overrides
+-- =============================================================================
+
+-- Feature Flags Table (Global)
+-- Flag definitions shared by all tenants. `enabled` turns the percentage rollout on;
+-- per-tenant overrides in tenant_feature_flags take precedence over both.
+CREATE TABLE IF NOT EXISTS feature_flags (
+    key VARCHAR(100) PRIMARY KEY,
+    description VARCHAR,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percentage SMALLINT NOT NULL DEFAULT 0 CHECK (rollout_percentage BETWEEN 0 AND 100),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Trigger for feature_flags updated_at
+DROP TRIGGER IF EXISTS update_feature_flags_updated_at ON feature_flags;
+CREATE TRIGGER update_feature_flags_updated_at
+    BEFORE UPDATE ON feature_flags
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Tenant Feature Flag Overrides Table (With RLS)
+CREATE TABLE IF NOT EXISTS tenant_feature_flags (
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    flag_key VARCHAR(100) NOT NULL REFERENCES feature_flags(key) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, flag_key)
+);
+
+-- Enable RLS on tenant_feature_flags
+ALTER TABLE tenant_feature_flags ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_policy ON tenant_feature_flags;
+CREATE POLICY tenant_isolation_policy ON tenant_feature_flags
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+-- Trigger for tenant_feature_flags updated_at
+DROP TRIGGER IF EXISTS update_tenant_feature_flags_updated_at ON tenant_feature_flags;
+CREATE TRIGGER update_tenant_feature_flags_updated_at
+    BEFORE UPDATE ON tenant_feature_flags
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,5 @@
+-- Revert 0005: Plans and tenant plan assignment
+
+ALTER TABLE tenants DROP COLUMN IF EXISTS plan_id;
+DROP FUNCTION IF EXISTS default_plan_id();
+DROP TABLE IF EXISTS plans;
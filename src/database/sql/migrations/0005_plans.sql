@@ -0,0 +1,42 @@
+-- =============================================================================
+-- 0005: Plans and tenant plan assignment
+-- =============================================================================
+
+-- Plans Table (Global)
+-- Commercial plans and their quotas. NULL limits mean unlimited.
+CREATE TABLE IF NOT EXISTS plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    max_users INTEGER CHECK (max_users >= 0),
+    max_api_keys INTEGER CHECK (max_api_keys >= 0),
+    max_requests_per_month BIGINT CHECK (max_requests_per_month >= 0),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Trigger for plans updated_at
+DROP TRIGGER IF EXISTS update_plans_updated_at ON plans;
+CREATE TRIGGER update_plans_updated_at
+    BEFORE UPDATE ON plans
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Default plans
+INSERT INTO plans (code, name, max_users, max_api_keys, max_requests_per_month) VALUES
+    ('free', 'Free', 5, 2, 100000),
+    ('pro', 'Pro', 100, 20, 10000000),
+    ('enterprise', 'Enterprise', NULL, NULL, NULL)
+ON CONFLICT (code) DO NOTHING;
+
+-- Plan assigned to tenants created without an explicit plan
+CREATE OR REPLACE FUNCTION default_plan_id()
+RETURNS UUID AS $$
+    SELECT id FROM plans WHERE code = 'free'
+$$ LANGUAGE sql STABLE;
+
+-- Plan reference on tenants
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS plan_id UUID REFERENCES plans(id);
+ALTER TABLE tenants ALTER COLUMN plan_id SET DEFAULT default_plan_id();
+UPDATE tenants SET plan_id = default_plan_id() WHERE plan_id IS NULL;
+ALTER TABLE tenants ALTER COLUMN plan_id SET NOT NULL;
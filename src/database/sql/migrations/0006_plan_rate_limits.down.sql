@@ -0,0 +1,3 @@
+-- Revert 0006: Per-plan rate limits
+
+ALTER TABLE plans DROP COLUMN IF EXISTS rate_limit_per_minute;
@@ -0,0 +1,8 @@
+-- =============================================================================
+-- 0006: Per-plan rate limits
+-- =============================================================================
+
+-- Per-minute request rate allowed for a tenant (NULL = unlimited)
+ALTER TABLE plans ADD COLUMN IF NOT EXISTS rate_limit_per_minute INTEGER CHECK (rate_limit_per_minute > 0);
+UPDATE plans SET rate_limit_per_minute = 60 WHERE code = 'free' AND rate_limit_per_minute IS NULL;
+UPDATE plans SET rate_limit_per_minute = 600 WHERE code = 'pro' AND rate_limit_per_minute IS NULL;
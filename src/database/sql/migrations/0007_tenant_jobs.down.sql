@@ -0,0 +1,4 @@
+-- Revert 0007: Tenant jobs and deletion tombstones
+
+DROP TABLE IF EXISTS tenant_tombstones;
+DROP TABLE IF EXISTS tenant_jobs;
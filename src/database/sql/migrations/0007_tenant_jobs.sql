@@ -0,0 +1,41 @@
+-- =============================================================================
+-- 0007: Tenant jobs and deletion tombstones
+-- =============================================================================
+
+-- Tenant Jobs Table (Global)
+-- Long-running admin operations (export, import, clone, hard deletion). Not tied to tenants(id)
+-- by a foreign key so the job history survives the tenant's deletion.
+CREATE TABLE IF NOT EXISTS tenant_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL,
+    kind VARCHAR NOT NULL CHECK (kind IN ('export', 'delete', 'import', 'clone')),
+    status VARCHAR NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    artifact_path VARCHAR,
+    result JSONB,
+    error VARCHAR,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    -- Refreshed while the job runs; a job whose heartbeat stopped was cut off by a
+    -- crash or restart and is marked failed
+    heartbeat_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS tenant_jobs_tenant_id_idx ON tenant_jobs (tenant_id);
+
+-- Tenant Tombstones Table (Global)
+-- Audit record of hard-deleted tenants. Holds no tenant data beyond row counts.
+-- A tenant id can be re-created (e.g. by an import) and deleted again, so each
+-- deletion gets its own tombstone.
+CREATE TABLE IF NOT EXISTS tenant_tombstones (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL,
+    plan_code VARCHAR(50),
+    row_counts JSONB NOT NULL,
+    reason VARCHAR,
+    deleted_by_job_id UUID,
+    tenant_created_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS tenant_tombstones_tenant_id_idx ON tenant_tombstones (tenant_id);
@@ -0,0 +1,22 @@
+-- Revert 0008: Runtime role and forced row level security
+-- The app_runtime role is cluster-wide and may serve other databases, so it is kept
+
+ALTER POLICY tenant_isolation_policy ON users
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+ALTER POLICY tenant_isolation_policy ON tenant_domains
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+ALTER POLICY tenant_isolation_policy ON tenant_settings
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+ALTER POLICY tenant_isolation_policy ON tenant_feature_flags
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER TABLE users NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_domains NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_settings NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_feature_flags NO FORCE ROW LEVEL SECURITY;
+
+ALTER DEFAULT PRIVILEGES IN SCHEMA public REVOKE SELECT, INSERT, UPDATE, DELETE ON TABLES FROM app_runtime;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public REVOKE USAGE, SELECT ON SEQUENCES FROM app_runtime;
+REVOKE ALL ON ALL TABLES IN SCHEMA public FROM app_runtime;
+REVOKE ALL ON ALL SEQUENCES IN SCHEMA public FROM app_runtime;
+REVOKE USAGE ON SCHEMA public FROM app_runtime;
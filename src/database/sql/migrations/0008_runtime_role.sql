@@ -0,0 +1,50 @@
+-- =============================================================================
+-- 0008: Runtime role and forced row level security
+-- =============================================================================
+
+-- Migrations run as the schema owner; the API connects as a login role that is a
+-- member of app_runtime. Owners and superusers bypass RLS, so the runtime role must
+-- be neither, and FORCE makes the policies apply to the owner as well.
+--
+-- Create the login role once per environment (requires CREATEROLE):
+--   CREATE ROLE app_user LOGIN PASSWORD '...' IN ROLE app_runtime;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_runtime') THEN
+        CREATE ROLE app_runtime NOLOGIN NOSUPERUSER NOBYPASSRLS;
+    END IF;
+END
+$$;
+
+-- Data access only: no DDL, and the migration history is read-only
+GRANT USAGE ON SCHEMA public TO app_runtime;
+GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO app_runtime;
+GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA public TO app_runtime;
+REVOKE INSERT, UPDATE, DELETE ON schema_migrations FROM app_runtime;
+
+-- Tables created by later migrations get the same grants
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO app_runtime;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT USAGE, SELECT ON SEQUENCES TO app_runtime;
+
+-- Tenant tables: RLS applies to the owner too, and writes must target the current tenant
+ALTER TABLE users FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_domains FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_settings FORCE ROW LEVEL SECURITY;
+ALTER TABLE tenant_feature_flags FORCE ROW LEVEL SECURITY;
+
+ALTER POLICY tenant_isolation_policy ON users
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_domains
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_settings
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_feature_flags
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
@@ -0,0 +1,19 @@
+-- Revert 0009: Fail-closed tenant context
+
+ALTER POLICY tenant_isolation_policy ON users
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_domains
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_settings
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+ALTER POLICY tenant_isolation_policy ON tenant_feature_flags
+    USING (tenant_id = current_setting('app.current_tenant_id', true)::uuid)
+    WITH CHECK (tenant_id = current_setting('app.current_tenant_id', true)::uuid);
+
+DROP FUNCTION IF EXISTS app_current_tenant_id();
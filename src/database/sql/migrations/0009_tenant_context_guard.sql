@@ -0,0 +1,47 @@
+-- =============================================================================
+-- 0009: Fail-closed tenant context
+-- =============================================================================
+
+-- Tenant id of the current transaction, as set by with_tenant.
+-- Without a tenant context the policies see NULL and match nothing; once a
+-- transaction-local set_config ends, the setting reads as '' instead of NULL,
+-- which used to fail with an unhelpful uuid cast error.
+-- In strict mode (app.strict_tenant_context = 'on', set per connection by the API)
+-- touching a tenant table without a tenant context raises TN000 instead.
+CREATE OR REPLACE FUNCTION app_current_tenant_id()
+RETURNS UUID AS $$
+DECLARE
+    tenant TEXT := current_setting('app.current_tenant_id', true);
+BEGIN
+    IF tenant IS NOT NULL AND tenant <> '' THEN
+        RETURN tenant::uuid;
+    END IF;
+
+    -- Host header resolution legitimately reads tenant_domains before a tenant is known
+    IF current_setting('app.strict_tenant_context', true) = 'on'
+        AND coalesce(current_setting('app.domain_resolution', true), '') <> 'on' THEN
+        RAISE EXCEPTION 'tenant context not set'
+            USING ERRCODE = 'TN000',
+                  HINT = 'Tenant tables must be queried inside DatabaseService::with_tenant';
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+-- The sub-select is evaluated once per statement rather than once per row
+ALTER POLICY tenant_isolation_policy ON users
+    USING (tenant_id = (SELECT app_current_tenant_id()))
+    WITH CHECK (tenant_id = (SELECT app_current_tenant_id()));
+
+ALTER POLICY tenant_isolation_policy ON tenant_domains
+    USING (tenant_id = (SELECT app_current_tenant_id()))
+    WITH CHECK (tenant_id = (SELECT app_current_tenant_id()));
+
+ALTER POLICY tenant_isolation_policy ON tenant_settings
+    USING (tenant_id = (SELECT app_current_tenant_id()))
+    WITH CHECK (tenant_id = (SELECT app_current_tenant_id()));
+
+ALTER POLICY tenant_isolation_policy ON tenant_feature_flags
+    USING (tenant_id = (SELECT app_current_tenant_id()))
+    WITH CHECK (tenant_id = (SELECT app_current_tenant_id()));
@@ -0,0 +1,276 @@
+// Tenant-scoped access to the connection pool
+
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Transaction};
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+
+use crate::database::tx_options::{is_retryable, RetryPolicy, TxOptions, TxTimeouts};
+
+/// SQLSTATE raised by `app_current_tenant_id()` in strict mode when a tenant
+/// table is queried outside a tenant transaction (see migration 0009)
+pub const MISSING_TENANT_CONTEXT: &str = "TN000";
+
+/// Handle to the pool that only hands out transactions bound to a tenant.
+///
+/// Tenant tables (see `TENANT_TABLES`) are meant to be reached only through this
+/// type; `DatabaseService::get_pool` is for global tables such as `tenants`,
+/// `plans` or `tenant_jobs`. With `DB_STRICT_TENANT_CONTEXT` the database enforces
+/// the split: a tenant-table query on a connection without a tenant context fails
+/// with `MISSING_TENANT_CONTEXT` instead of silently matching no rows.
+#[derive(Clone, Debug)]
+pub struct TenantScopedPool {
+    pool: PgPool,
+    /// Default for `with_tenant_retry` when the call does not override it
+    retry: RetryPolicy,
+    /// Defaults for the timeouts a transaction does not override
+    timeouts: TxTimeouts,
+}
+
+impl TenantScopedPool {
+    pub(crate) fn new(pool: PgPool, retry: RetryPolicy, timeouts: TxTimeouts) -> Self {
+        Self { pool, retry, timeouts }
+    }
+
+    /// Begins a transaction with `app.current_tenant_id` set for its duration.
+    /// The setting is transaction-local, so it never leaks to the next user of the connection.
+    pub async fn begin(&self, tenant_id: Uuid) -> Result<TenantTx> {
+        self.begin_with(tenant_id, TxOptions::default()).await
+    }
+
+    /// Like `begin`, with an explicit isolation level, access mode and timeouts
+    pub async fn begin_with(&self, tenant_id: Uuid, options: TxOptions) -> Result<TenantTx> {
+        let mut tx: Transaction<'static, Postgres> = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        // Must be the first statement of the transaction
+        if let Some(sql) = options.set_transaction_sql() {
+            sqlx::query(&sql)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to set transaction isolation level")?;
+        }
+
+        // SET cannot take bind parameters, so use set_config(..., is_local = true) instead.
+        // The backend pid and transaction start identify the transaction for cancellation.
+        let [statement_timeout, lock_timeout, idle_timeout]: [String; 3] = options.timeouts.or(self.timeouts).as_settings();
+        let (backend_pid, started_at): (i32, DateTime<Utc>) = sqlx::query_as(
+            r#"
+            SELECT pg_backend_pid(), now()
+            FROM (
+                SELECT set_config('app.current_tenant_id', $1, true),
+                       set_config('statement_timeout', $2, true),
+                       set_config('lock_timeout', $3, true),
+                       set_config('idle_in_transaction_session_timeout', $4, true)
+            ) AS applied
+            "#
+        )
+        .bind(tenant_id.to_string())
+        .bind(statement_timeout)
+        .bind(lock_timeout)
+        .bind(idle_timeout)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to set tenant context")?;
+
+        let cancel: CancelOnDrop = CancelOnDrop { connect_options: self.pool.connect_options(), backend_pid, started_at, armed: true };
+        Ok(TenantTx { tenant_id, tx, cancel })
+    }
+
+    /// Executes a closure within a tenant-scoped transaction.
+    /// The transaction is committed if the closure returns Ok and rolled back otherwise.
+    pub async fn with_tenant<F, T>(&self, tenant_id: Uuid, block: F) -> Result<T>
+    where
+        F: for<'c> FnOnce(&'c mut Transaction<'_, Postgres>) -> Pin<Box<dyn Future<Output = Result<T>> + Send + 'c>> + Send,
+    {
+        let mut tx: TenantTx = self.begin(tenant_id).await?;
+
+        match block(&mut tx.tx).await {
+            Ok(val) => {
+                tx.commit().await?;
+                Ok(val)
+            }
+            Err(e) => {
+                // Rollback is automatic on drop, but explicit rollback is good for logging
+                let _ = tx.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Executes a closure within a tenant-scoped transaction using `options`, re-running
+    /// the whole transaction after serialization failures (40001) and deadlocks (40P01).
+    ///
+    /// The closure may run several times, so it must not have side effects outside
+    /// the transaction; clone captured values inside it instead of moving them out.
+    /// Attempts are recorded on the `tenant_tx` tracing span.
+    pub async fn with_tenant_retry<F, T>(&self, tenant_id: Uuid, options: TxOptions, block: F) -> Result<T>
+    where
+        F: for<'c> Fn(&'c mut Transaction<'_, Postgres>) -> Pin<Box<dyn Future<Output = Result<T>> + Send + 'c>> + Send + Sync,
+    {
+        let policy: RetryPolicy = options.retry.unwrap_or(self.retry);
+        let span: Span = tracing::info_span!(
+            "tenant_tx",
+            %tenant_id,
+            isolation = options.isolation.as_sql(),
+            retries = tracing::field::Empty,
+        );
+
+        async {
+            let mut retries: u32 = 0;
+            loop {
+                let result: Result<T> = async {
+                    let mut tx: TenantTx = self.begin_with(tenant_id, options).await?;
+                    match block(&mut tx.tx).await {
+                        Ok(val) => {
+                            tx.commit().await?;
+                            Ok(val)
+                        }
+                        Err(e) => {
+                            let _ = tx.rollback().await;
+                            Err(e)
+                        }
+                    }
+                }.await;
+
+                match result {
+                    Err(e) if retries < policy.max_retries && is_retryable(&e) => {
+                        retries += 1;
+                        let delay: Duration = policy.backoff(retries);
+                        tracing::debug!("Retrying tenant transaction ({}/{}) in {:?}: {}", retries, policy.max_retries, delay, e);
+                        tokio::time::sleep(delay).await;
+                    }
+                    result => {
+                        Span::current().record("retries", retries);
+                        if retries > 0 {
+                            match &result {
+                                Ok(_) => tracing::info!("Tenant transaction succeeded after {} retries", retries),
+                                Err(e) => tracing::warn!("Tenant transaction failed after {} retries: {}", retries, e),
+                            }
+                        }
+                        return result;
+                    }
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// A transaction bound to one tenant.
+///
+/// Derefs to the connection, so `&mut *tx` is an executor:
+/// `sqlx::query("SELECT ...").fetch_one(&mut *tx)`. Nothing is persisted until
+/// `commit()`; dropping the guard rolls the transaction back and cancels the
+/// statement still running on it, if any (e.g. when a request times out).
+#[derive(Debug)]
+pub struct TenantTx {
+    tenant_id: Uuid,
+    tx: Transaction<'static, Postgres>,
+    cancel: CancelOnDrop,
+}
+
+impl TenantTx {
+    pub fn tenant_id(&self) -> Uuid {
+        self.tenant_id
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        let TenantTx { tx, mut cancel, .. } = self;
+        cancel.armed = false;
+        tx.commit().await.context("Failed to commit transaction")
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        let TenantTx { tx, mut cancel, .. } = self;
+        cancel.armed = false;
+        tx.rollback().await.context("Failed to roll back transaction")
+    }
+}
+
+impl Deref for TenantTx {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        &self.tx
+    }
+}
+
+impl DerefMut for TenantTx {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+}
+
+/// Cancels the statement a dropped `TenantTx` is still running.
+///
+/// Dropping a query future does not stop the query: sqlx drains the connection before
+/// returning it to the pool, so it stays busy until the query finishes. The cancel
+/// request goes over a dedicated connection opened outside the pool, so it still gets
+/// through when the pool is exhausted (typically by the very queries to cancel).
+#[derive(Debug)]
+struct CancelOnDrop {
+    connect_options: Arc<PgConnectOptions>,
+    backend_pid: i32,
+    started_at: DateTime<Utc>,
+    /// Cleared by `commit` / `rollback`
+    armed: bool,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let (connect_options, backend_pid, started_at): (Arc<PgConnectOptions>, i32, DateTime<Utc>) =
+            (self.connect_options.clone(), self.backend_pid, self.started_at);
+        runtime.spawn(async move {
+            // Only signal the backend while it is still running a statement of this
+            // transaction, so a connection already reused by another request is left alone
+            let cancelled: Result<Option<bool>, sqlx::Error> = async {
+                let mut conn: PgConnection = PgConnection::connect_with(&connect_options).await?;
+                let cancelled: Option<bool> = sqlx::query_scalar(
+                    r#"
+                    SELECT pg_cancel_backend(pid)
+                    FROM pg_stat_activity
+                    WHERE pid = $1 AND xact_start = $2 AND state = 'active'
+                    "#
+                )
+                .bind(backend_pid)
+                .bind(started_at)
+                .fetch_optional(&mut conn)
+                .await?;
+                let _ = conn.close().await;
+                Ok(cancelled)
+            }.await;
+
+            match cancelled {
+                Ok(Some(true)) => tracing::info!("Cancelled running statement of dropped tenant transaction (backend {})", backend_pid),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to cancel statement of dropped tenant transaction: {}", e),
+            }
+        });
+    }
+}
+
+/// True if the error comes from querying a tenant table without a tenant context
+pub fn is_missing_tenant_context(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|e| e.downcast_ref::<sqlx::Error>())
+        .filter_map(|e: &sqlx::Error| e.as_database_error())
+        .any(|e| e.code().as_deref() == Some(MISSING_TENANT_CONTEXT))
+}
+
+// End of file: /src/database/tenant_pool.rs
@@ -0,0 +1,65 @@
+// Registry of tenant-scoped tables for export, import and deletion
+
+/// Kind of personal data a column holds, deciding its anonymized replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiiKind {
+    Email,
+    Name,
+    /// Credentials; replaced by a value that can never be used
+    Secret,
+}
+
+/// A table whose rows belong to a tenant (protected by `tenant_isolation_policy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantTable {
+    pub name: &'static str,
+    /// ORDER BY clause giving exports a stable row order
+    pub order_by: &'static str,
+    /// UUID primary key regenerated on import (None when keyed by tenant_id)
+    pub id_column: Option<&'static str>,
+    /// UUID columns pointing at another tenant table's id column, remapped on import
+    pub references: &'static [&'static str],
+    /// Columns replaced when importing with anonymization
+    pub pii_columns: &'static [(&'static str, PiiKind)],
+}
+
+/// Every tenant-scoped table, parents before children.
+/// Export and import walk this list forwards and deletion walks it backwards,
+/// so a new tenant table must be registered here or those will miss its rows.
+pub const TENANT_TABLES: &[TenantTable] = &[
+    TenantTable {
+        name: "users",
+        order_by: "created_at, id",
+        id_column: Some("id"),
+        references: &[],
+        pii_columns: &[("email", PiiKind::Email), ("full_name", PiiKind::Name), ("password_hash", PiiKind::Secret)],
+    },
+    TenantTable {
+        name: "tenant_domains",
+        order_by: "created_at, id",
+        id_column: Some("id"),
+        references: &[],
+        pii_columns: &[],
+    },
+    TenantTable {
+        name: "tenant_settings",
+        order_by: "tenant_id",
+        id_column: None,
+        references: &[],
+        pii_columns: &[],
+    },
+    TenantTable {
+        name: "tenant_feature_flags",
+        order_by: "flag_key",
+        id_column: None,
+        references: &[],
+        pii_columns: &[],
+    },
+];
+
+/// Looks up a registered table by name
+pub fn find_tenant_table(name: &str) -> Option<&'static TenantTable> {
+    TENANT_TABLES.iter().find(|t: &&TenantTable| t.name == name)
+}
+
+// End of file: /src/database/tenant_tables.rs
@@ -0,0 +1,238 @@
+// Transaction options: isolation level, access mode, timeouts and retry policy
+
+use std::time::Duration;
+use rand::Rng;
+
+/// SQLSTATE of serialization failures (SERIALIZABLE / REPEATABLE READ conflicts)
+pub const SERIALIZATION_FAILURE: &str = "40001";
+
+/// SQLSTATE of detected deadlocks
+pub const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Upper bound for a single backoff delay
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// PostgreSQL default; no `SET TRANSACTION` is issued
+    #[default]
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// How often and how patiently a transaction is re-run after a serialization
+/// failure or deadlock. Delays grow exponentially from `base_delay` with jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_retries: 0, base_delay: Duration::ZERO }
+    }
+
+    /// Delay before retry number `retry` (1-based): a random point in the upper
+    /// half of `base_delay * 2^(retry - 1)`, capped at one second
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponential: Duration = self.base_delay
+            .saturating_mul(1u32 << (retry.saturating_sub(1)).min(16))
+            .min(MAX_RETRY_DELAY);
+        let half: u64 = exponential.as_micros() as u64 / 2;
+        let jitter: u64 = rand::thread_rng().gen_range(0..=half);
+        Duration::from_micros(half + jitter)
+    }
+}
+
+/// Server-side timeouts of one transaction, applied with `SET LOCAL` semantics.
+/// `None` keeps the configured default; `Duration::ZERO` disables the timeout.
+///
+/// Routes override the defaults for `RequestTx` by adding the value as an extension:
+/// `get(handler).layer(Extension(TxTimeouts::new().statement(Duration::from_secs(60))))`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxTimeouts {
+    /// `statement_timeout`: longest single statement
+    pub statement: Option<Duration>,
+    /// `lock_timeout`: longest wait for a row or table lock
+    pub lock: Option<Duration>,
+    /// `idle_in_transaction_session_timeout`: longest pause between statements
+    pub idle_in_transaction: Option<Duration>,
+}
+
+impl TxTimeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn statement(mut self, timeout: Duration) -> Self {
+        self.statement = Some(timeout);
+        self
+    }
+
+    pub fn lock(mut self, timeout: Duration) -> Self {
+        self.lock = Some(timeout);
+        self
+    }
+
+    pub fn idle_in_transaction(mut self, timeout: Duration) -> Self {
+        self.idle_in_transaction = Some(timeout);
+        self
+    }
+
+    /// Fills the timeouts not set here from `defaults`
+    pub fn or(self, defaults: TxTimeouts) -> Self {
+        Self {
+            statement: self.statement.or(defaults.statement),
+            lock: self.lock.or(defaults.lock),
+            idle_in_transaction: self.idle_in_transaction.or(defaults.idle_in_transaction),
+        }
+    }
+
+    /// `set_config` values in milliseconds; unset timeouts become "0" (disabled)
+    pub(crate) fn as_settings(&self) -> [String; 3] {
+        let millis = |timeout: Option<Duration>| -> String {
+            format!("{}ms", timeout.unwrap_or_default().as_millis())
+        };
+        [millis(self.statement), millis(self.lock), millis(self.idle_in_transaction)]
+    }
+}
+
+/// Options for `TenantScopedPool::with_tenant_retry` and `begin_with`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TxOptions {
+    pub isolation: IsolationLevel,
+    pub read_only: bool,
+    /// Overrides the configured retry policy (`DB_TX_MAX_RETRIES`, `DB_TX_RETRY_BASE_DELAY_MS`)
+    pub retry: Option<RetryPolicy>,
+    /// Overrides the configured timeouts (`DB_STATEMENT_TIMEOUT_MS`, ...)
+    pub timeouts: TxTimeouts,
+}
+
+impl TxOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn isolation(mut self, isolation: IsolationLevel) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn timeouts(mut self, timeouts: TxTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// `SET TRANSACTION` statement for these options, if any differ from the defaults
+    pub(crate) fn set_transaction_sql(&self) -> Option<String> {
+        if self.isolation == IsolationLevel::ReadCommitted && !self.read_only {
+            return None;
+        }
+        let access: &str = if self.read_only { "READ ONLY" } else { "READ WRITE" };
+        Some(format!("SET TRANSACTION ISOLATION LEVEL {}, {}", self.isolation.as_sql(), access))
+    }
+}
+
+/// True if the error is a serialization failure or deadlock, i.e. re-running
+/// the whole transaction may succeed
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|e| e.downcast_ref::<sqlx::Error>())
+        .filter_map(|e: &sqlx::Error| e.as_database_error())
+        .any(|e| matches!(e.code().as_deref(), Some(SERIALIZATION_FAILURE) | Some(DEADLOCK_DETECTED)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(base_delay_ms: u64) -> RetryPolicy {
+        RetryPolicy { max_retries: 5, base_delay: Duration::from_millis(base_delay_ms) }
+    }
+
+    #[test]
+    fn backoff_stays_in_the_upper_half_of_the_exponential_delay() {
+        let policy: RetryPolicy = policy(10);
+        for retry in 1..=6u32 {
+            let exponential: Duration = Duration::from_millis(10 * (1 << (retry - 1)));
+            for _ in 0..200 {
+                let delay: Duration = policy.backoff(retry);
+                assert!(delay >= exponential / 2 && delay <= exponential, "retry {}: {:?}", retry, delay);
+            }
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped_at_one_second() {
+        let policy: RetryPolicy = policy(300);
+        for retry in [3, 16, 17, u32::MAX] {
+            let delay: Duration = policy.backoff(retry);
+            assert!(delay >= MAX_RETRY_DELAY / 2 && delay <= MAX_RETRY_DELAY, "retry {}: {:?}", retry, delay);
+        }
+    }
+
+    #[test]
+    fn backoff_treats_retry_zero_as_the_first_retry() {
+        let delay: Duration = policy(10).backoff(0);
+        assert!(delay >= Duration::from_millis(5) && delay <= Duration::from_millis(10), "{:?}", delay);
+    }
+
+    #[test]
+    fn backoff_jitters() {
+        let policy: RetryPolicy = policy(100);
+        let delays: std::collections::HashSet<Duration> = (0..50).map(|_| policy.backoff(1)).collect();
+        assert!(delays.len() > 1, "50 delays were all {:?}", delays);
+    }
+
+    #[test]
+    fn no_delay_without_a_base_delay() {
+        assert_eq!(RetryPolicy::none().backoff(1), Duration::ZERO);
+        assert_eq!(policy(0).backoff(10), Duration::ZERO);
+    }
+
+    #[test]
+    fn timeouts_fall_back_to_the_defaults_and_disable_unset_ones() {
+        let defaults: TxTimeouts = TxTimeouts::new().statement(Duration::from_secs(15)).lock(Duration::from_secs(5));
+        let timeouts: TxTimeouts = TxTimeouts::new().statement(Duration::from_secs(60)).or(defaults);
+        assert_eq!(timeouts.as_settings(), ["60000ms", "5000ms", "0ms"]);
+    }
+
+    #[test]
+    fn set_transaction_only_when_options_differ_from_the_defaults() {
+        assert_eq!(TxOptions::new().set_transaction_sql(), None);
+        assert_eq!(
+            TxOptions::new().read_only().set_transaction_sql().as_deref(),
+            Some("SET TRANSACTION ISOLATION LEVEL READ COMMITTED, READ ONLY")
+        );
+        assert_eq!(
+            TxOptions::new().isolation(IsolationLevel::Serializable).set_transaction_sql().as_deref(),
+            Some("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE, READ WRITE")
+        );
+    }
+}
+
+// End of file: /src/database/tx_options.rs
@@ -1,5 +1,6 @@
 // Main application entry point
 
+use std::net::SocketAddr;
 use axum::serve;
 
 use my_axum_project::config::state::AppState;
@@ -8,19 +9,47 @@ use my_axum_project::core::{logging, server};
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     logging::init_tracing();
-    
-    // Initialize database with master schema and tenants table
-    AppState::init_master_schema().await?;
-    
-    let app: axum::Router = server::create_app();
-    let listener: tokio::net::TcpListener = server::setup_listener().await?;
+
+    let state: AppState = AppState::from_env()?;
+
+    // Initialize database with master schema and tenants table (unless probes should be
+    // answered while Postgres and the cache come up)
+    let serve_before_ready: bool = state.environment.startup_serve_before_ready;
+    if !serve_before_ready {
+        state.init_master_schema().await?;
+    }
+
+    let app: axum::Router = server::create_app(state.clone());
+    let listener: tokio::net::TcpListener = server::setup_listener(&state.environment).await?;
 
     println!("Server listening on: {}", listener.local_addr()?);
 
-    // Start server with graceful shutdown handling
-    serve(listener, app)
-        .with_graceful_shutdown(server::shutdown_signal())
-        .await?;
+    // Start server with graceful shutdown handling (peer address feeds IP rate limits)
+    let server = serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(server::shutdown_signal());
 
+    if serve_before_ready {
+        // Readiness turns green once initialization finishes; if it gives up, so does the process
+        tokio::select! {
+            result = server => result?,
+            error = initialize_in_background(&state) => {
+                state.shutdown().await;
+                return Err(error);
+            }
+        }
+    } else {
+        server.await?;
+    }
+
+    // Gracefully close database connections
+    state.shutdown().await;
     Ok(())
 }
+
+/// Runs `init_master_schema` next to the server, resolving only if it fails
+async fn initialize_in_background(state: &AppState) -> anyhow::Error {
+    match state.init_master_schema().await {
+        Ok(()) => std::future::pending().await,
+        Err(e) => e,
+    }
+}
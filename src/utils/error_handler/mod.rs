@@ -1,4 +1,5 @@
 // Error handling module
 
+#[allow(clippy::module_inception)]
 pub mod error_handler;
 pub use error_handler::*;
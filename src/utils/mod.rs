@@ -9,6 +9,7 @@
 
 pub mod error_handler;
 pub mod response_handler;
+#[allow(clippy::module_inception)]
 pub mod utils;
 
 // End of file: /src/utils/mod.rs
\ No newline at end of file
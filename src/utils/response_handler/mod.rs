@@ -1,4 +1,5 @@
 // Response handling module
 
+#[allow(clippy::module_inception)]
 pub mod response_handler;
 pub use response_handler::*;
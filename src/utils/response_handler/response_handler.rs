@@ -74,6 +74,11 @@ impl IntoResponse for HandlerResponse {
     }
 }
 
+/// Marks a response whose body must reach the client untouched (e.g. file downloads).
+/// Insert it into the response extensions to bypass `response_wrapper`.
+#[derive(Debug, Clone, Copy)]
+pub struct RawBody;
+
 fn create_default_status_message(parts: &Parts) -> String {
     parts.status
         .canonical_reason()
@@ -120,6 +125,10 @@ pub async fn response_wrapper(
 ) -> Result<Response<Body>, Infallible> {
     let response: Response<Body> = next.run(req).await;
 
+    if response.extensions().get::<RawBody>().is_some() {
+        return Ok(response);
+    }
+
     let (messages, data) = extract_response_components(&response);
     let (parts, _) = response.into_parts();
 
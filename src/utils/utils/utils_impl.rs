@@ -12,4 +12,37 @@ pub fn to_two_space_indented_json<T: Serialize>(value: &T) -> Result<String> {
     Ok(pretty_json)
 }
 
+// Stable 64-bit FNV-1a hash over a sequence of byte slices.
+// Unlike std's DefaultHasher, the output never changes across builds or platforms.
+pub fn fnv1a_64(parts: &[&[u8]]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash: u64 = FNV_OFFSET_BASIS;
+    for part in parts {
+        for byte in *part {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_64_matches_the_reference_vectors() {
+        assert_eq!(fnv1a_64(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(&[b"foobar"]), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn fnv1a_64_hashes_the_concatenated_parts() {
+        assert_eq!(fnv1a_64(&[b"foo", b"", b"bar"]), fnv1a_64(&[b"foobar"]));
+    }
+}
+
 // End of file: /src/utils/utils/utils_impl.rs
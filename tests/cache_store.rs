@@ -0,0 +1,124 @@
+// CacheStore semantics, checked against the in-memory backend
+//
+//   cargo test --test cache_store
+// Needs neither Redis nor Postgres.
+
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{ensure, Context, Result};
+use uuid::Uuid;
+
+use my_axum_project::database::{CacheService, CacheStore, CacheSubscription, MemoryStore, Session};
+
+// =============================================================================
+// STORE
+// =============================================================================
+
+#[tokio::test]
+async fn values_expire_after_their_ttl() -> Result<()> {
+    let store: MemoryStore = MemoryStore::new();
+    store.set("short", "1", Some(Duration::from_millis(20))).await?;
+    store.set("forever", "2", None).await?;
+
+    ensure!(store.get("short").await?.as_deref() == Some("1"), "value missing before expiry");
+    tokio::time::sleep(Duration::from_millis(40)).await;
+
+    ensure!(store.get("short").await?.is_none(), "value outlived its ttl");
+    ensure!(!store.exists("short").await?, "expired key still exists");
+    ensure!(store.get_many(&["short".into(), "forever".into()]).await? == vec![None, Some("2".to_string())], "unexpected get_many");
+    Ok(())
+}
+
+#[tokio::test]
+async fn incr_counts_from_zero_and_delete_reports_existing_keys() -> Result<()> {
+    let store: MemoryStore = MemoryStore::new();
+    let ttl: Duration = Duration::from_secs(60);
+
+    ensure!(store.incr("counter", ttl).await? == 1, "first incr should return 1");
+    ensure!(store.incr("counter", ttl).await? == 2, "second incr should return 2");
+
+    store.set("text", "not a number", None).await?;
+    ensure!(store.incr("text", ttl).await.is_err(), "incr on a non-integer should fail");
+
+    let removed: u64 = store.delete(&["counter".into(), "text".into(), "missing".into()]).await?;
+    ensure!(removed == 2, "expected 2 removed keys, got {}", removed);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scan_matches_glob_patterns() -> Result<()> {
+    let store: MemoryStore = MemoryStore::new();
+    for key in ["tenant:a", "tenant:a:settings", "tenant:b:plan", "session:x"] {
+        store.set(key, "1", None).await?;
+    }
+
+    let mut keys: Vec<String> = store.scan("tenant:a*").await?;
+    keys.sort();
+    ensure!(keys == ["tenant:a", "tenant:a:settings"], "unexpected scan result {:?}", keys);
+
+    let mut keys: Vec<String> = store.scan("tenant:*:plan").await?;
+    keys.sort();
+    ensure!(keys == ["tenant:b:plan"], "unexpected scan result {:?}", keys);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rate_limit_admits_a_burst_then_rejects() -> Result<()> {
+    let store: MemoryStore = MemoryStore::new();
+    let (now, interval, tolerance): (i64, i64, i64) = (1_000_000, 1_000, 2_000);
+
+    // Tolerance of two intervals admits three requests at once
+    for attempt in 0..3 {
+        let (allowed, _) = store.rate_limit("bucket", now, interval, tolerance).await?;
+        ensure!(allowed, "request {} should be admitted", attempt);
+    }
+    let (allowed, tat) = store.rate_limit("bucket", now, interval, tolerance).await?;
+    ensure!(!allowed && tat == now + 3 * interval, "fourth request should be rejected (tat {})", tat);
+
+    let (allowed, _) = store.rate_limit("bucket", now + interval, interval, tolerance).await?;
+    ensure!(allowed, "one interval later a request should be admitted again");
+    Ok(())
+}
+
+#[tokio::test]
+async fn published_messages_reach_every_subscriber() -> Result<()> {
+    let store: MemoryStore = MemoryStore::new();
+    let mut first: CacheSubscription = store.subscribe("invalidate").await?;
+    let mut second: CacheSubscription = store.subscribe("invalidate").await?;
+    let mut other: CacheSubscription = store.subscribe("other").await?;
+
+    store.publish("invalidate", "flags").await?;
+
+    ensure!(first.recv().await.as_deref() == Some("flags"), "first subscriber missed the message");
+    ensure!(second.recv().await.as_deref() == Some("flags"), "second subscriber missed the message");
+    ensure!(other.try_recv().is_err(), "message leaked to another channel");
+    Ok(())
+}
+
+// =============================================================================
+// SERVICE
+// =============================================================================
+
+#[tokio::test]
+async fn purge_tenant_removes_only_that_tenants_keys_and_sessions() -> Result<()> {
+    let cache: CacheService = CacheService::new(Arc::new(MemoryStore::new()));
+    let (tenant_a, tenant_b): (Uuid, Uuid) = (Uuid::new_v4(), Uuid::new_v4());
+
+    for tenant_id in [tenant_a, tenant_b] {
+        cache.set_tenant(&tenant_id).await?;
+        cache.incr_monthly_requests(&tenant_id, "2026-01").await?;
+        let session: Session = Session { user_id: Uuid::new_v4(), tenant_id, email: "user@example.com".into() };
+        cache.create_session(&tenant_id.to_string(), &session, 60).await?;
+    }
+
+    let removed: u64 = cache.purge_tenant(&tenant_a, &[]).await?;
+    ensure!(removed == 3, "expected 3 removed keys, got {}", removed);
+
+    ensure!(!cache.tenant_exists(&tenant_a).await?, "tenant a is still cached");
+    ensure!(cache.get_session(&tenant_a.to_string()).await?.is_none(), "tenant a's session survived");
+    ensure!(cache.tenant_exists(&tenant_b).await?, "tenant b was purged too");
+    let session: Session = cache.get_session(&tenant_b.to_string()).await?.context("tenant b's session was purged")?;
+    ensure!(session.tenant_id == tenant_b, "unexpected session {:?}", session);
+    ensure!(cache.get_monthly_requests(&tenant_b, "2026-01").await? == 1, "tenant b's counter was purged");
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// Circuit breaker state transitions
+//
+//   cargo test --test circuit_breaker
+// Needs neither Redis nor Postgres.
+
+use std::time::Duration;
+use anyhow::{ensure, Result};
+
+use my_axum_project::database::{CircuitBreaker, CircuitState};
+
+const COOLDOWN: Duration = Duration::from_millis(30);
+
+#[tokio::test]
+async fn opens_after_consecutive_failures_only() -> Result<()> {
+    let breaker: CircuitBreaker = CircuitBreaker::new(3, COOLDOWN);
+
+    breaker.on_failure();
+    breaker.on_failure();
+    breaker.on_success();
+    breaker.on_failure();
+    breaker.on_failure();
+    ensure!(breaker.state() == CircuitState::Closed, "a success should reset the failure count");
+    ensure!(breaker.try_acquire(), "closed breaker should admit calls");
+
+    breaker.on_failure();
+    ensure!(breaker.state() == CircuitState::Open, "third consecutive failure should open the breaker");
+    ensure!(!breaker.try_acquire(), "open breaker should fail calls fast");
+    ensure!(breaker.opened_count() == 1, "unexpected opened count {}", breaker.opened_count());
+    Ok(())
+}
+
+#[tokio::test]
+async fn admits_one_trial_after_the_cooldown() -> Result<()> {
+    let breaker: CircuitBreaker = CircuitBreaker::new(1, COOLDOWN);
+    breaker.on_failure();
+    tokio::time::sleep(COOLDOWN * 2).await;
+
+    ensure!(breaker.try_acquire(), "cooldown over, a trial call should be admitted");
+    ensure!(breaker.state() == CircuitState::HalfOpen, "breaker should be half-open during the trial");
+    ensure!(!breaker.try_acquire(), "only one trial call at a time");
+
+    // A failed trial reopens the breaker for another cooldown
+    breaker.on_failure();
+    ensure!(breaker.state() == CircuitState::Open && !breaker.try_acquire(), "failed trial should reopen the breaker");
+    ensure!(breaker.opened_count() == 2, "unexpected opened count {}", breaker.opened_count());
+
+    tokio::time::sleep(COOLDOWN * 2).await;
+    ensure!(breaker.try_acquire(), "second trial should be admitted");
+    breaker.on_success();
+    ensure!(breaker.state() == CircuitState::Closed, "successful trial should close the breaker");
+    ensure!(breaker.try_acquire() && breaker.try_acquire(), "closed breaker should admit every call");
+    Ok(())
+}